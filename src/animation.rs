@@ -0,0 +1,341 @@
+//! Non-blocking animations
+//!
+//! An [`Effect`] computes a value for each output at a point in time and an
+//! [`Animator`] writes those values to a set of [`Output`]s whenever
+//! [`Animator::tick`] is called, so animations run alongside other work in
+//! the same loop.
+//!
+//! Targets that update all at once, such as LED strips, use a [`Renderer`]
+//! to fill a frame buffer instead.
+
+use crate::devices::rgb_led::{Rgb, RgbLed};
+use crate::utils::Duty;
+use embedded_hal::PwmPin;
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+use switch_hal::OutputSwitch;
+
+pub mod effects;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps progress `t` (0.0..=1.0) to eased progress
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - 2.0 * (1.0 - t) * (1.0 - t)
+                }
+            }
+        }
+    }
+
+    /// Steepest slope of the curve
+    pub fn peak_slope(self) -> f64 {
+        match self {
+            Easing::Linear => 1.0,
+            _ => 2.0,
+        }
+    }
+}
+
+/// Values that can be blended
+pub trait Lerp: Copy {
+    fn lerp(self, other: Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(self, other: Self, t: f64) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Lerp for Rgb {
+    fn lerp(self, other: Self, t: f64) -> Self {
+        Rgb::lerp(self, other, t)
+    }
+}
+
+/// Produces a value for output `index` of `count`, `t` after the start
+pub trait Effect<V> {
+    fn value(&mut self, t: Duration, index: usize, count: usize) -> V;
+}
+
+impl<V, F> Effect<V> for Box<F>
+where
+    F: Effect<V> + ?Sized,
+{
+    fn value(&mut self, t: Duration, index: usize, count: usize) -> V {
+        (**self).value(t, index, count)
+    }
+}
+
+/// Something an animation can drive
+pub trait Output<V> {
+    type Error;
+
+    fn set(&mut self, value: V) -> Result<(), Self::Error>;
+}
+
+/// An on/off output, on at levels of `0.5` and above
+///
+/// Polarity comes from the switch, e.g.
+/// `Digital(pin.into_active_low_switch())` for LEDs wired to the supply.
+pub struct Digital<S>(pub S);
+
+impl<S> Output<f64> for Digital<S>
+where
+    S: OutputSwitch,
+{
+    type Error = S::Error;
+
+    fn set(&mut self, level: f64) -> Result<(), S::Error> {
+        if level >= 0.5 {
+            self.0.on()
+        } else {
+            self.0.off()
+        }
+    }
+}
+
+/// A `PwmPin` as a brightness output, `0.0..=1.0`
+pub struct Pwm<P>(pub P);
+
+impl<P> Output<f64> for Pwm<P>
+where
+    P: PwmPin,
+    P::Duty: Duty,
+{
+    type Error = Infallible;
+
+    fn set(&mut self, level: f64) -> Result<(), Infallible> {
+        let duty = Duty::from_ratio(self.0.get_max_duty(), level);
+        self.0.set_duty(duty);
+        Ok(())
+    }
+}
+
+impl<R, G, B> Output<Rgb> for RgbLed<R, G, B>
+where
+    R: PwmPin,
+    G: PwmPin,
+    B: PwmPin,
+    R::Duty: Duty,
+    G::Duty: Duty,
+    B::Duty: Duty,
+{
+    type Error = Infallible;
+
+    fn set(&mut self, color: Rgb) -> Result<(), Infallible> {
+        self.set_color(color);
+        Ok(())
+    }
+}
+
+/// A single transition from `from` to `to`
+#[derive(Copy, Clone, Debug)]
+pub struct Tween<V> {
+    pub from: V,
+    pub to: V,
+    pub duration: Duration,
+    pub easing: Easing,
+}
+
+impl<V: Lerp> Tween<V> {
+    pub fn new(from: V, to: V, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            easing,
+        }
+    }
+
+    pub fn value_at(&self, t: Duration) -> V {
+        if t >= self.duration {
+            return self.to;
+        }
+
+        let progress = t.as_secs_f64() / self.duration.as_secs_f64();
+        self.from.lerp(self.to, self.easing.apply(progress))
+    }
+}
+
+impl<V: Lerp> Effect<V> for Tween<V> {
+    fn value(&mut self, t: Duration, _index: usize, _count: usize) -> V {
+        self.value_at(t)
+    }
+}
+
+/// Values at points in time, eased in between
+pub struct Keyframes<V> {
+    frames: Vec<(Duration, V)>,
+    easing: Easing,
+    looping: bool,
+}
+
+impl<V: Lerp> Keyframes<V> {
+    /// Creates new `Keyframes`, `frames` must be sorted by time
+    ///
+    /// # Panics
+    ///
+    /// If `frames` is empty.
+    pub fn new(frames: Vec<(Duration, V)>, easing: Easing, looping: bool) -> Self {
+        assert!(!frames.is_empty(), "at least one keyframe");
+
+        Self {
+            frames,
+            easing,
+            looping,
+        }
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames[self.frames.len() - 1].0
+    }
+
+    pub fn value_at(&self, t: Duration) -> V {
+        let duration = self.duration();
+        let t = if self.looping && duration > Duration::default() {
+            Duration::from_secs_f64(t.as_secs_f64() % duration.as_secs_f64())
+        } else {
+            t
+        };
+
+        let next = self.frames.iter().position(|(at, _)| *at > t);
+        match next {
+            None => self.frames[self.frames.len() - 1].1,
+            Some(0) => self.frames[0].1,
+            Some(i) => {
+                let (start, from) = self.frames[i - 1];
+                let (end, to) = self.frames[i];
+                let tween = Tween::new(from, to, end - start, self.easing);
+                tween.value_at(t - start)
+            }
+        }
+    }
+}
+
+impl<V: Lerp> Effect<V> for Keyframes<V> {
+    fn value(&mut self, t: Duration, _index: usize, _count: usize) -> V {
+        self.value_at(t)
+    }
+}
+
+/// Runs an [`Effect`] on a set of [`Output`]s
+pub struct Animator<O, F, V> {
+    outputs: Vec<O>,
+    effect: F,
+    started: Instant,
+    _value: PhantomData<V>,
+}
+
+impl<O, F, V> Animator<O, F, V>
+where
+    O: Output<V>,
+    F: Effect<V>,
+{
+    pub fn new(outputs: Vec<O>, effect: F, now: Instant) -> Self {
+        Self {
+            outputs,
+            effect,
+            started: now,
+            _value: PhantomData,
+        }
+    }
+
+    /// Swaps the effect and starts it from the beginning
+    pub fn set_effect(&mut self, effect: F, now: Instant) {
+        self.effect = effect;
+        self.started = now;
+    }
+
+    pub fn restart(&mut self, now: Instant) {
+        self.started = now;
+    }
+
+    /// Writes the effect's current values to every output
+    pub fn tick(&mut self, now: Instant) -> Result<(), O::Error> {
+        let t = now.saturating_duration_since(self.started);
+        let count = self.outputs.len();
+
+        for (i, output) in self.outputs.iter_mut().enumerate() {
+            output.set(self.effect.value(t, i, count))?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets every output to `value`, e.g. to turn everything off
+    pub fn fill(&mut self, value: V) -> Result<(), O::Error>
+    where
+        V: Copy,
+    {
+        for output in self.outputs.iter_mut() {
+            output.set(value)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn outputs_mut(&mut self) -> &mut [O] {
+        &mut self.outputs
+    }
+
+    pub fn into_outputs(self) -> Vec<O> {
+        self.outputs
+    }
+}
+
+/// Runs an [`Effect`] into a frame buffer
+pub struct Renderer<F> {
+    effect: F,
+    started: Instant,
+}
+
+impl<F> Renderer<F> {
+    pub fn new(effect: F, now: Instant) -> Self {
+        Self {
+            effect,
+            started: now,
+        }
+    }
+
+    /// Swaps the effect and starts it from the beginning
+    pub fn set_effect(&mut self, effect: F, now: Instant) {
+        self.effect = effect;
+        self.started = now;
+    }
+
+    pub fn restart(&mut self, now: Instant) {
+        self.started = now;
+    }
+
+    /// Writes the effect's current values into `frame`
+    pub fn render<V>(&mut self, now: Instant, frame: &mut [V])
+    where
+        F: Effect<V>,
+    {
+        let t = now.saturating_duration_since(self.started);
+        let count = frame.len();
+
+        for (i, value) in frame.iter_mut().enumerate() {
+            *value = self.effect.value(t, i, count);
+        }
+    }
+}
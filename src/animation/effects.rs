@@ -0,0 +1,212 @@
+//! Built-in effects
+
+use super::Effect;
+use crate::devices::rgb_led::Rgb;
+use std::f64::consts::PI;
+use std::time::Duration;
+
+/// Colours a brightness effect, e.g. a red [`Chase`] on an LED strip
+pub struct Tint<F> {
+    pub effect: F,
+    pub color: Rgb,
+}
+
+impl<F> Tint<F> {
+    pub fn new(effect: F, color: Rgb) -> Self {
+        Self { effect, color }
+    }
+}
+
+impl<F> Effect<Rgb> for Tint<F>
+where
+    F: Effect<f64>,
+{
+    fn value(&mut self, t: Duration, index: usize, count: usize) -> Rgb {
+        self.color.scale(self.effect.value(t, index, count))
+    }
+}
+
+/// Smooth fade up and down, like `breathing_led`
+pub struct Breathe {
+    pub period: Duration,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Breathe {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            min: 0.0,
+            max: 1.0,
+        }
+    }
+}
+
+impl Effect<f64> for Breathe {
+    fn value(&mut self, t: Duration, _index: usize, _count: usize) -> f64 {
+        let phase = t.as_secs_f64() / self.period.as_secs_f64();
+        let level = (1.0 - (2.0 * PI * phase).cos()) / 2.0;
+
+        self.min + (self.max - self.min) * level
+    }
+}
+
+/// One light running along the outputs, like `light_water`
+pub struct Chase {
+    pub step: Duration,
+    /// Run back instead of jumping to the first output
+    pub bounce: bool,
+}
+
+impl Chase {
+    pub fn new(step: Duration, bounce: bool) -> Self {
+        Self { step, bounce }
+    }
+
+    /// Index of the lit output
+    pub fn position(&self, t: Duration, count: usize) -> usize {
+        if count < 2 {
+            return 0;
+        }
+
+        let n = (t.as_secs_f64() / self.step.as_secs_f64()) as usize;
+        if self.bounce {
+            let n = n % (2 * count - 2);
+            if n < count {
+                n
+            } else {
+                2 * count - 2 - n
+            }
+        } else {
+            n % count
+        }
+    }
+}
+
+impl Effect<f64> for Chase {
+    fn value(&mut self, t: Duration, index: usize, count: usize) -> f64 {
+        if self.position(t, count) == index {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Repeating on/off pattern
+pub struct Blink {
+    /// Alternating on and off durations, starting with on
+    pattern: Vec<Duration>,
+}
+
+impl Blink {
+    /// # Panics
+    ///
+    /// If `pattern` is empty.
+    pub fn new(pattern: Vec<Duration>) -> Self {
+        assert!(!pattern.is_empty(), "pattern needs at least one step");
+        Self { pattern }
+    }
+
+    pub fn steady(on: Duration, off: Duration) -> Self {
+        Self::new(vec![on, off])
+    }
+
+    pub fn is_on(&self, t: Duration) -> bool {
+        let total: f64 = self.pattern.iter().map(|d| d.as_secs_f64()).sum();
+        if total <= 0.0 {
+            return false;
+        }
+
+        let mut t = t.as_secs_f64() % total;
+        for (i, d) in self.pattern.iter().enumerate() {
+            let d = d.as_secs_f64();
+            if t < d {
+                return i % 2 == 0;
+            }
+            t -= d;
+        }
+
+        false
+    }
+}
+
+impl Effect<f64> for Blink {
+    fn value(&mut self, t: Duration, _index: usize, _count: usize) -> f64 {
+        if self.is_on(t) {
+            1.0
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Cycles the hue, spreading it across the outputs
+pub struct Rainbow {
+    pub period: Duration,
+    /// Fraction of the colour wheel between neighbouring outputs
+    pub spread: f64,
+    pub brightness: f64,
+}
+
+impl Rainbow {
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            spread: 0.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl Effect<Rgb> for Rainbow {
+    fn value(&mut self, t: Duration, index: usize, _count: usize) -> Rgb {
+        let phase = t.as_secs_f64() / self.period.as_secs_f64() + self.spread * index as f64;
+        Rgb::from_hsv(phase * 360.0, 1.0, self.brightness)
+    }
+}
+
+/// A new random colour every `interval`, cross-faded over `fade`
+pub struct RandomColor {
+    pub interval: Duration,
+    pub fade: Duration,
+    from: Rgb,
+    to: Rgb,
+    changed: Option<Duration>,
+}
+
+impl RandomColor {
+    pub fn new(interval: Duration, fade: Duration) -> Self {
+        Self {
+            interval,
+            fade,
+            from: Rgb::BLACK,
+            to: Rgb::BLACK,
+            changed: None,
+        }
+    }
+}
+
+impl Effect<Rgb> for RandomColor {
+    fn value(&mut self, t: Duration, _index: usize, _count: usize) -> Rgb {
+        // also pick a new colour when the animation was restarted
+        let changed = match self.changed {
+            Some(c) if c <= t && t - c < self.interval => c,
+            _ => {
+                self.from = self.to;
+                self.to = Rgb::new(rand::random(), rand::random(), rand::random());
+                self.changed = Some(t);
+                t
+            }
+        };
+
+        let elapsed = t - changed;
+        if elapsed >= self.fade {
+            self.to
+        } else {
+            let progress = elapsed.as_secs_f64() / self.fade.as_secs_f64();
+            self.from.lerp(self.to, progress)
+        }
+    }
+}
@@ -1,33 +1,35 @@
 use anyhow::Result;
+use rpizw_test::animation::{effects::Breathe, Animator, Pwm as PwmOutput};
 use rppal::pwm::{Channel, Polarity, Pwm};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
 
 const DELAY: u64 = 30;
 const FREQUENCY: f64 = 120.0;
 const DUTY_CYCLE: f64 = 0.0;
+const PERIOD: Duration = Duration::from_millis(6000);
 
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
 
     let led = Pwm::with_frequency(Channel::Pwm0, FREQUENCY, DUTY_CYCLE, Polarity::Normal, true)?;
+    let mut animator = Animator::new(vec![PwmOutput(led)], Breathe::new(PERIOD), Instant::now());
 
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })?;
 
     while running.load(Ordering::SeqCst) {
-        for i in 0..100 {
-            led.set_duty_cycle(i as f64 / 100.0)?;
-            sleep(Duration::from_millis(DELAY));
-        }
-        for i in (1..99).rev() {
-            led.set_duty_cycle(i as f64 / 100.0)?;
-            sleep(Duration::from_millis(DELAY));
-        }
+        animator.tick(Instant::now())?;
+        sleep(Duration::from_millis(DELAY));
     }
 
+    animator.fill(0.0)?;
+
     Ok(())
 }
@@ -0,0 +1,62 @@
+use anyhow::{bail, Result};
+use rpizw_test::devices::buzzer::{rtttl, Buzzer, ToneOutput};
+use rpizw_test::utils::soft_pwm::SoftPwm;
+use rppal::gpio::Gpio;
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+// for the `soft` mode, `pwm` uses PWM0 on GPIO 18
+const BUZZER_PIN: u8 = 16;
+const MELODY: &str = "Mario:d=4,o=5,b=100:16e6,16e6,32p,8e6,16c6,8e6,8g6,8p,8g,8p";
+
+fn run<T, E>(output: T, melody: &str, running: &AtomicBool) -> Result<()>
+where
+    T: ToneOutput<Error = E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let ringtone = rtttl::parse(melody)?;
+    println!("playing {}", ringtone.name);
+
+    let mut buzzer = Buzzer::new(output)?;
+    buzzer.play(ringtone.notes, Instant::now())?;
+
+    // the loop is free to do other work while the melody plays
+    while running.load(Ordering::SeqCst) && buzzer.is_playing() {
+        buzzer.tick(Instant::now())?;
+        sleep(Duration::from_millis(1));
+    }
+
+    buzzer.stop()?;
+    Ok(())
+}
+
+// usage: buzzer [pwm|soft] [rtttl]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let melody = std::env::args()
+        .nth(2)
+        .unwrap_or_else(|| MELODY.to_string());
+
+    match std::env::args().nth(1).as_deref() {
+        None | Some("pwm") => {
+            let pwm = Pwm::with_frequency(Channel::Pwm0, 1000.0, 0.0, Polarity::Normal, false)?;
+            run(pwm, &melody, &running)
+        }
+        Some("soft") => {
+            let pin = Gpio::new()?.get(BUZZER_PIN)?.into_output();
+            run(SoftPwm::new(pin, 1000.0), &melody, &running)
+        }
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+}
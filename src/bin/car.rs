@@ -1,19 +1,34 @@
-use anyhow::Result;
+use anyhow::{bail, Result};
+use embedded_hal::adc::OneShot;
 use gilrs::{Axis, Button, EventType, Gilrs};
+use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
+use rpizw_test::devices::button;
+use rpizw_test::devices::buzzer::{self, Buzzer};
+use rpizw_test::devices::ir::remote::{Key, KeyEvent, Remote};
+use rpizw_test::devices::ir::IrReceiver;
 use rpizw_test::devices::motor::{Command, Motor};
+use rpizw_test::devices::servo::motion::{Easing, ServoMotion};
+use rpizw_test::devices::servo::{self, Servo};
+use rpizw_test::devices::ssd1306::telemetry::{Page, Telemetry};
+use rpizw_test::devices::ssd1306::{DisplaySize, I2cInterface, DEFAULT_ADDR, SSD1306};
+use rpizw_test::utils::convert_nb_error;
+use rpizw_test::utils::soft_pwm::SoftPwm;
 use rppal::gpio::Gpio;
+use rppal::i2c::I2c;
 use rppal::pwm::{Channel, Polarity, Pwm};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::sync::{mpsc, Arc};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use switch_hal::IntoSwitch;
 
 // the servo SG90 uses 50 Hz frequency, so it's 1 / 50 = 0.02 s = 20 ms
 const SERVO_PERIOD: Duration = Duration::from_millis(20);
-const SERVO_PULSE_WIDTH_MIN: Duration = Duration::from_micros(500);
-const SERVO_PULSE_WIDTH_MAX: Duration = Duration::from_micros(2500);
-const SERVO_ANGLE_MIN: u32 = 0;
-const SERVO_ANGLE_MAX: u32 = 180;
-const SERVO_ANGLE_OFFSET: i32 = -10;
+const SERVO_ANGLE_OFFSET: f64 = -10.0;
+// degrees per second, well below the SG90's 600 to avoid current spikes
+const SERVO_MAX_SPEED: f64 = 300.0;
 
 // motor
 const MOTOR_IN_1: u8 = 13;
@@ -21,32 +36,202 @@ const MOTOR_IN_2: u8 = 26;
 const MOTOR_FREQUENCY: f64 = 120.0;
 const MOTOR_DUTY_CYCLE: f64 = 0.0;
 
-// our car only can turn from -25 to 25 degrees ( using 0 as servo's 90 degrees)
-const STEERING_MIN_ANGLE: i32 = -30;
-const STEERING_MAX_ANGLE: i32 = 30;
+// our car only can turn from -30 to 30 degrees ( using 0 as servo's 90 degrees)
+const STEERING_MIN_ANGLE: f64 = -30.0;
+const STEERING_MAX_ANGLE: f64 = 30.0;
 
-fn angle_to_pulse_width(angle: i32) -> Duration {
-    let angle = match angle {
-        a if a > STEERING_MAX_ANGLE => STEERING_MAX_ANGLE,
-        a if a < STEERING_MIN_ANGLE => STEERING_MIN_ANGLE,
-        _ => angle,
-    };
+// the start button, to ground
+const START_BUTTON: u8 = 23;
+
+// the IR receiver's output, for the `ir` mode
+const IR_PIN: u8 = 24;
+// IR keys are on or off, so they drive at a fixed throttle
+const IR_THROTTLE: f64 = 0.6;
+// with no frame for this long the remote is out of range or put down, and
+// the latched throttle is dropped
+const IR_LINK_TIMEOUT: Duration = Duration::from_secs(3);
+
+// a passive piezo, both hardware PWM channels are taken
+const BUZZER_PIN: u8 = 16;
+// a 2S pack, 3.5 V per cell
+const LOW_BATTERY: f64 = 7.0;
+const LOW_BATTERY_REMINDER: Duration = Duration::from_secs(60);
 
-    // convert to servo angle
-    let servo_angle: u32 = (90 + SERVO_ANGLE_OFFSET - angle) as u32;
-    let ratio =
-        (SERVO_PULSE_WIDTH_MAX - SERVO_PULSE_WIDTH_MIN) / (SERVO_ANGLE_MAX - SERVO_ANGLE_MIN);
+// dashboard
+const ADC_ADDR: u8 = 0x4b;
+// the battery is measured through a 20k / 10k divider
+const BATTERY_DIVIDER: f64 = 3.0;
+const DASHBOARD_REFRESH: Duration = Duration::from_millis(200);
 
-    SERVO_PULSE_WIDTH_MIN + ratio * servo_angle
+struct Dashboard {
+    oled: SSD1306<I2cInterface<I2c>>,
+    adc: ADS7830<I2c>,
+    ch: Single<CH0>,
+    telemetry: Telemetry,
 }
 
-fn axis_to_pulse_width(v: f32) -> Duration {
-    let ratio = (STEERING_MAX_ANGLE - STEERING_MIN_ANGLE) as f32 / 2.0;
-    let angle = -1.0 + ratio * v;
+impl Dashboard {
+    fn new() -> Result<Self> {
+        let oled = SSD1306::new(
+            I2cInterface::new(I2c::new()?, DEFAULT_ADDR),
+            DisplaySize::Display128x64,
+        )?;
+        let adc = ADS7830::new(I2c::new()?, ADC_ADDR, Reference::Internal);
 
-    angle_to_pulse_width(angle as i32)
+        let mut telemetry = Telemetry::new();
+        telemetry.add_page(
+            Page::new("CAR")
+                .field("BAT", "V", 2)
+                .gauge("THR", -1.0, 1.0)
+                .gauge("STR", STEERING_MIN_ANGLE, STEERING_MAX_ANGLE)
+                .field("LINK", "", 0),
+        );
+
+        Ok(Self {
+            oled,
+            adc,
+            ch: Single::new(),
+            telemetry,
+        })
+    }
+
+    /// Redraws the dashboard, returns the battery voltage if it was read
+    fn refresh(&mut self) -> Result<Option<f64>> {
+        let voltage = convert_nb_error(self.adc.read(&mut self.ch))?
+            .map(|v| v as f64 / 255.0 * 3.3 * BATTERY_DIVIDER);
+        if let Some(voltage) = voltage {
+            self.telemetry.set_number("BAT", voltage);
+        }
+
+        self.telemetry.draw(&mut self.oled)?;
+        self.oled.flush()?;
+        Ok(voltage)
+    }
 }
 
+/// What the car is told to do, by a gamepad or an IR remote
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Control {
+    /// -1.0 (left) to 1.0 (right)
+    Steer(f64),
+    /// -1.0 (reverse) to 1.0 (forward)
+    Throttle(f64),
+    Break,
+    Coast,
+    Link(bool),
+}
+
+enum Controller {
+    Gamepad(Gilrs),
+    Ir {
+        receiver: IrReceiver,
+        remote: Remote<Control>,
+        /// When the last frame arrived, `None` once the link is lost
+        last_frame: Option<Instant>,
+    },
+}
+
+impl Controller {
+    fn gamepad() -> Result<Self> {
+        let gilrs = Gilrs::new().unwrap();
+
+        // Iterate over all connected gamepads
+        for (_id, gamepad) in gilrs.gamepads() {
+            println!("{} is {:?}", gamepad.name(), gamepad.power_info());
+        }
+
+        Ok(Controller::Gamepad(gilrs))
+    }
+
+    fn ir() -> Result<Self> {
+        let receiver = IrReceiver::new(Gpio::new()?.get(IR_PIN)?.into_input())?;
+
+        // the 2, 8, 4, 6 and 5 keys of the common 21 key NEC remote
+        let mut remote = Remote::new();
+        let key = |command| Key::Nec {
+            address: 0x00,
+            command,
+        };
+        remote.bind(key(0x18), Control::Throttle(IR_THROTTLE));
+        remote.bind(key(0x52), Control::Throttle(-IR_THROTTLE));
+        remote.bind(key(0x08), Control::Steer(-1.0));
+        remote.bind(key(0x5a), Control::Steer(1.0));
+        remote.bind(key(0x1c), Control::Break);
+
+        Ok(Controller::Ir {
+            receiver,
+            remote,
+            last_frame: None,
+        })
+    }
+
+    fn connected(&self) -> bool {
+        match self {
+            Controller::Gamepad(gilrs) => gilrs.gamepads().next().is_some(),
+            Controller::Ir { last_frame, .. } => last_frame.is_some(),
+        }
+    }
+
+    fn controls(&mut self, now: Instant) -> Vec<Control> {
+        let mut controls = Vec::new();
+
+        match self {
+            Controller::Gamepad(gilrs) => {
+                while let Some(event) = gilrs.next_event() {
+                    controls.push(match event.event {
+                        EventType::Connected => Control::Link(true),
+                        EventType::Disconnected => Control::Link(false),
+                        EventType::AxisChanged(Axis::LeftStickX, v, ..) => Control::Steer(v as f64),
+                        EventType::ButtonChanged(Button::LeftTrigger2, v, ..) => {
+                            Control::Throttle(-v as f64)
+                        }
+                        EventType::ButtonChanged(Button::RightTrigger2, v, ..) => {
+                            Control::Throttle(v as f64)
+                        }
+                        EventType::ButtonPressed(Button::South, ..) => Control::Break,
+                        EventType::ButtonReleased(Button::South, ..) => Control::Coast,
+                        _ => continue,
+                    });
+                }
+            }
+            Controller::Ir {
+                receiver,
+                remote,
+                last_frame,
+            } => {
+                for frame in receiver.poll(now) {
+                    // print the codes, to bind the keys of other remotes
+                    if Key::from_frame(frame).is_some() {
+                        println!("ir: {:?}", frame);
+                    }
+                    if last_frame.is_none() {
+                        controls.push(Control::Link(true));
+                    }
+                    *last_frame = Some(now);
+                    remote.feed(frame, now);
+                }
+                remote.tick(now);
+
+                if last_frame.is_some_and(|at| now.duration_since(at) >= IR_LINK_TIMEOUT) {
+                    *last_frame = None;
+                    controls.push(Control::Link(false));
+                }
+
+                // the throttle latches until Break or the opposite key,
+                // only the steering springs back
+                controls.extend(remote.events().filter_map(|event| match event {
+                    KeyEvent::Pressed(control) => Some(control),
+                    KeyEvent::Released(Control::Steer(_)) => Some(Control::Steer(0.0)),
+                    KeyEvent::Released(_) => None,
+                }));
+            }
+        }
+
+        controls
+    }
+}
+
+// usage: car [gamepad|ir]
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
@@ -55,21 +240,29 @@ fn main() -> Result<()> {
         r.store(false, Ordering::SeqCst);
     })?;
 
-    let mut gilrs = Gilrs::new().unwrap();
-
-    // Iterate over all connected gamepads
-    for (_id, gamepad) in gilrs.gamepads() {
-        println!("{} is {:?}", gamepad.name(), gamepad.power_info());
-    }
+    let mut controller = match std::env::args().nth(1).as_deref() {
+        None | Some("gamepad") => Controller::gamepad()?,
+        Some("ir") => Controller::ir()?,
+        Some(other) => bail!("Unknown mode {}", other),
+    };
 
     // servo
-    let servo = Pwm::with_period(
+    let config = servo::Config {
+        period: SERVO_PERIOD,
+        trim: SERVO_ANGLE_OFFSET,
+        limits: (90.0 + STEERING_MIN_ANGLE, 90.0 + STEERING_MAX_ANGLE),
+        inverted: true,
+        ..Default::default()
+    };
+    let pwm = Pwm::with_period(
         Channel::Pwm0,
         SERVO_PERIOD,
-        angle_to_pulse_width(0),
+        config.pulse_width(config.center()),
         Polarity::Normal,
         true,
     )?;
+    let mut steering =
+        ServoMotion::new(Servo::new(pwm, config)?, SERVO_MAX_SPEED, Easing::EaseInOut);
 
     // motor
     let in1 = Gpio::new()?.get(MOTOR_IN_1)?.into_output();
@@ -83,39 +276,133 @@ fn main() -> Result<()> {
     )?;
     let mut motor = Motor::l298(in1, in2, pwm)?;
 
+    // the car drives fine without a dashboard
+    let mut dashboard = match Dashboard::new() {
+        Ok(dashboard) => Some(dashboard),
+        Err(e) => {
+            println!("no dashboard: {}", e);
+            None
+        }
+    };
+    // the motor only runs after the start button is clicked, clicking it
+    // again stops the car
+    let (sender, buttons) = mpsc::channel();
+    let pin = Gpio::new()?.get(START_BUTTON)?.into_input_pullup();
+    button::Button::new(pin.into_active_low_switch(), button::Config::default())
+        .spawn(Duration::from_millis(5), sender);
+    let mut started = false;
+
+    let pin = Gpio::new()?.get(BUZZER_PIN)?.into_output();
+    let mut buzzer = Buzzer::new(SoftPwm::new(pin, 1000.0))?;
+    let mut beeping = false;
+    let mut warned: Option<Instant> = None;
+
+    let mut connected = controller.connected();
+    let mut throttle = 0.0;
+    let mut refreshed = Instant::now();
+
     while running.load(Ordering::SeqCst) {
-        while let Some(event) = gilrs.next_event() {
-            match event.event {
-                EventType::AxisChanged(Axis::LeftStickX, v, ..) => {
-                    let pw = axis_to_pulse_width(v);
-                    println!("steer!, v={}, pw={:?}", v, pw);
-                    servo.set_pulse_width(pw)?;
+        for event in buttons.try_iter() {
+            if event == button::Event::Click {
+                started = !started;
+                println!("{}", if started { "start!" } else { "stop!" });
+                if started {
+                    buzzer.play(buzzer::startup_chirp(), Instant::now())?;
                 }
-                EventType::ButtonChanged(Button::LeftTrigger2, v, ..) => {
-                    println!("reverse!, v={}", v);
-                    motor.run(Command::ClockWise, v as f64)?;
+                if !started {
+                    throttle = 0.0;
+                    motor.run(Command::Coast, 0.0)?;
                 }
-                EventType::ButtonChanged(Button::RightTrigger2, v, ..) => {
+            }
+        }
+
+        for control in controller.controls(Instant::now()) {
+            match control {
+                Control::Link(true) => connected = true,
+                Control::Link(false) => {
+                    println!("controller lost, stopping");
+                    connected = false;
+                    throttle = 0.0;
+                    motor.run(Command::Coast, 0.0)?;
+                }
+                Control::Steer(v) => {
+                    steering.set_target_normalized(v, Instant::now())?;
+                    println!("steer!, v={}, angle={}", v, steering.target());
+                }
+                Control::Throttle(v) if started && v < 0.0 => {
+                    println!("reverse!, v={}", -v);
+                    motor.run(Command::ClockWise, -v)?;
+                    throttle = v;
+                }
+                Control::Throttle(v) if started => {
                     println!("forward!, v={}", v);
-                    motor.run(Command::CounterClockWise, v as f64)?;
+                    motor.run(Command::CounterClockWise, v)?;
+                    throttle = v;
                 }
-                EventType::ButtonPressed(Button::South, ..) => {
+                Control::Throttle(_) => {}
+                Control::Break => {
                     println!("break!");
                     motor.run(Command::Break, 1.0)?;
+                    throttle = 0.0;
                 }
-                EventType::ButtonReleased(Button::South, ..) => {
+                Control::Coast => {
                     println!("coast!");
                     motor.run(Command::Coast, 0.0)?;
+                    throttle = 0.0;
+                }
+            }
+        }
+        steering.tick(Instant::now());
+
+        // beep while reversing, like a truck
+        if (throttle < 0.0) != beeping {
+            beeping = throttle < 0.0;
+            if beeping {
+                buzzer.play_repeat(buzzer::reverse_beeper(), Instant::now())?;
+            } else {
+                buzzer.stop()?;
+            }
+        }
+        buzzer.tick(Instant::now())?;
+
+        if let Some(dash) = dashboard.as_mut() {
+            if refreshed.elapsed() >= DASHBOARD_REFRESH {
+                let telemetry = &mut dash.telemetry;
+                telemetry.set_number("THR", throttle);
+                telemetry.set_number("STR", steering.servo().angle() - 90.0);
+                telemetry.set_text("LINK", if connected { "OK" } else { "LOST" });
+                refreshed = Instant::now();
+
+                match dash.refresh() {
+                    Ok(voltage) => {
+                        let low = voltage.is_some_and(|v| v < LOW_BATTERY);
+                        let due = warned.is_none_or(|at| at.elapsed() >= LOW_BATTERY_REMINDER);
+                        if low && due && !beeping {
+                            println!("low battery!");
+                            buzzer.queue(buzzer::low_battery(), Instant::now())?;
+                            warned = Some(Instant::now());
+                        }
+                    }
+                    Err(e) => {
+                        // a loose display or ADC must not stop the car
+                        println!("dashboard lost: {}", e);
+                        dashboard = None;
+                    }
                 }
-                _ => {}
             }
         }
+
         sleep(Duration::from_millis(10));
     }
 
-    servo.set_pulse_width(axis_to_pulse_width(0.0))?;
     motor.run(Command::Coast, 0.0)?;
-    sleep(Duration::from_millis(10));
+    buzzer.stop()?;
+
+    // centre within the speed limit, the motor is already coasting
+    steering.set_target_normalized(0.0, Instant::now())?;
+    while steering.tick(Instant::now()) {
+        sleep(Duration::from_millis(10));
+    }
 
     Ok(())
 }
@@ -0,0 +1,79 @@
+use anyhow::{bail, Context, Result};
+use embedded_hal::adc::OneShot;
+use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
+use rpizw_test::devices::dac::{self, mcp4725, pcf8591, Dac, MCP4725, PCF8591};
+use rpizw_test::utils::convert_nb_error;
+use rppal::i2c::I2c;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{thread::sleep, time::Duration};
+
+const ADC_ADDR: u8 = 0x4b;
+const ADC_REFERENCE: f64 = 3.3;
+// the DAC is powered from 3.3V for the loopback, from 5V it can drive a
+// motor controller's 0-5 V speed input
+const DAC_REFERENCE: f64 = 3.3;
+const STEPS: u32 = 10;
+const DELAY: u64 = 500;
+
+/// Steps the DAC's output up, the ADC's CH0 reads it back
+fn calibrate<D, E>(dac: &mut D, running: &AtomicBool) -> Result<()>
+where
+    D: Dac<Error = E>,
+    E: std::error::Error + Send + Sync + 'static,
+{
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mut adc = ADS7830::new(i2c, ADC_ADDR, Reference::Internal);
+    let mut ch: Single<CH0> = Single::new();
+
+    for step in (0..=STEPS).cycle() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let voltage = dac.set_voltage(dac.reference() * f64::from(step) / f64::from(STEPS))?;
+        sleep(Duration::from_millis(DELAY));
+
+        if let Some(v) = convert_nb_error(adc.read(&mut ch))? {
+            let measured = dac::code_to_voltage(v.into(), 255, ADC_REFERENCE);
+            println!(
+                "set: {:.3} V, measured: {:.3} V, error: {:+.3} V",
+                voltage,
+                measured,
+                measured - voltage
+            );
+        }
+    }
+
+    dac.set_code(0)?;
+    Ok(())
+}
+
+// usage: dac [mcp4725|pcf8591]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let i2c = I2c::new().context("Failed to init I2C")?;
+
+    match std::env::args().nth(1).as_deref() {
+        None | Some("mcp4725") => {
+            let mut dac = MCP4725::new(i2c, mcp4725::DEFAULT_ADDR, DAC_REFERENCE)?;
+            println!("{:?}", dac.status()?);
+            calibrate(&mut dac, &running)?;
+            dac.power_down(mcp4725::PowerDown::Pull1k)?;
+        }
+        Some("pcf8591") => {
+            let mut dac = PCF8591::new(i2c, pcf8591::DEFAULT_ADDR, DAC_REFERENCE);
+            calibrate(&mut dac, &running)?;
+            dac.disable_output()?;
+        }
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+
+    Ok(())
+}
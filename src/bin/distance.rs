@@ -0,0 +1,60 @@
+use anyhow::{bail, Result};
+use rpizw_test::devices::hcsr04::{self, Echo, Error, InterruptEcho, PollingEcho, HCSR04};
+use rppal::gpio::{Gpio, OutputPin};
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{thread::sleep, time::Duration};
+
+const TRIG_PIN: u8 = 23;
+// through a 1k / 2k divider, the sensor's ECHO is 5V
+const ECHO_PIN: u8 = 24;
+const CELSIUS: f64 = 20.0;
+const SAMPLES: usize = 5;
+const DELAY: u64 = 200;
+
+fn run<ECHO>(sensor: &mut HCSR04<OutputPin, ECHO>, running: &AtomicBool) -> Result<()>
+where
+    ECHO: Echo<Error = Infallible>,
+{
+    while running.load(Ordering::SeqCst) {
+        match sensor.measure_median(SAMPLES) {
+            Ok(distance) => println!("Distance: {:.1} cm", distance * 100.0),
+            Err(Error::OutOfRange) => println!("Distance: out of range"),
+            Err(e) => return Err(e.into()),
+        }
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    Ok(())
+}
+
+// usage: distance [interrupt|poll]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let gpio = Gpio::new()?;
+    let trig = gpio.get(TRIG_PIN)?.into_output();
+    let echo = gpio.get(ECHO_PIN)?.into_input();
+    let config = hcsr04::Config {
+        celsius: CELSIUS,
+        ..Default::default()
+    };
+
+    match std::env::args().nth(1).as_deref() {
+        None | Some("interrupt") => {
+            let mut sensor = HCSR04::new(trig, InterruptEcho::new(echo)?, config)?;
+            run(&mut sensor, &running)
+        }
+        Some("poll") => {
+            let mut sensor = HCSR04::new(trig, PollingEcho(echo), config)?;
+            run(&mut sensor, &running)
+        }
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+}
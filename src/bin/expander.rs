@@ -0,0 +1,124 @@
+use anyhow::{bail, Context, Result};
+use rpizw_test::devices::expander::mcp23017::{self, Interrupt};
+use rpizw_test::devices::expander::{pcf8574, Input, Output, MCP23017, PCF8574};
+use rpizw_test::devices::motor::{Drive, Motor};
+use rpizw_test::devices::stepper_motor::{owned::OwnedStepperMotor, Dir};
+use rpizw_test::utils::lock;
+use rppal::gpio::{Gpio, Trigger};
+use rppal::i2c::I2c;
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{thread::sleep, time::Duration};
+
+// the motor's IN1 and IN2 on GPA0 and GPA1 (or P0 and P1), PWM stays on the Pi
+const MOTOR_IN_1: u8 = 0;
+const MOTOR_IN_2: u8 = 1;
+const MOTOR_FREQUENCY: f64 = 120.0;
+const MOTOR_SPEED: f64 = 0.5;
+// a button to ground on GPA7 reverses the motor
+const BUTTON: u8 = 7;
+// the stepper's coils on GPB0..GPB3
+const STEPPER_PINS: [u8; 4] = [8, 9, 10, 11];
+const STEPPER_DELAY: Duration = Duration::from_millis(3);
+// the MCP23017's INTA
+const INT_PIN: u8 = 17;
+
+fn motor_pwm() -> Result<Pwm> {
+    Ok(Pwm::with_frequency(
+        Channel::Pwm1,
+        MOTOR_FREQUENCY,
+        0.0,
+        Polarity::Normal,
+        true,
+    )?)
+}
+
+fn mcp23017(running: &AtomicBool) -> Result<()> {
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mcp = MCP23017::new(i2c, mcp23017::DEFAULT_ADDR)?.into_shared();
+
+    let in1 = Output::new(&mcp, MOTOR_IN_1)?;
+    let in2 = Output::new(&mcp, MOTOR_IN_2)?;
+    let mut motor = Motor::l298(in1, in2, motor_pwm()?)?;
+
+    let coils = [
+        Output::new(&mcp, STEPPER_PINS[0])?,
+        Output::new(&mcp, STEPPER_PINS[1])?,
+        Output::new(&mcp, STEPPER_PINS[2])?,
+        Output::new(&mcp, STEPPER_PINS[3])?,
+    ];
+    let mut stepper = OwnedStepperMotor::new(coils, 0, Dir::CCW, STEPPER_DELAY)?;
+
+    let _button = Input::new(&mcp, BUTTON)?;
+    {
+        let mut mcp = lock(&mcp);
+        mcp.set_pull_up(BUTTON, true)?;
+        mcp.set_polarity(BUTTON, true)?;
+        mcp.set_interrupt(BUTTON, Interrupt::Change)?;
+        // clear anything pending from before
+        mcp.read_port()?;
+    }
+    let mut int = Gpio::new()?.get(INT_PIN)?.into_input_pullup();
+    int.set_interrupt(Trigger::FallingEdge)?;
+
+    let mut speed = MOTOR_SPEED;
+    motor.set_speed(speed)?;
+
+    while running.load(Ordering::SeqCst) {
+        // INT stays low until the capture is read
+        if int.poll_interrupt(true, Some(Duration::ZERO))?.is_some() {
+            let captured = lock(&mcp).interrupt_capture()?;
+            if captured & 1 << BUTTON != 0 {
+                speed = -speed;
+                println!("reverse!, speed={}", speed);
+                motor.set_speed(speed)?;
+            }
+        }
+
+        stepper.step()?;
+    }
+
+    motor.stop()?;
+    stepper.release()?;
+
+    Ok(())
+}
+
+fn pcf8574(running: &AtomicBool) -> Result<()> {
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let pcf = PCF8574::new(i2c, pcf8574::DEFAULT_ADDR)?.into_shared();
+
+    // the L298's inputs only need the PCF8574's weak high
+    let in1 = Output::new(&pcf, MOTOR_IN_1)?;
+    let in2 = Output::new(&pcf, MOTOR_IN_2)?;
+    let mut motor = Motor::l298(in1, in2, motor_pwm()?)?;
+
+    let mut speed = MOTOR_SPEED;
+    while running.load(Ordering::SeqCst) {
+        println!("speed={}", speed);
+        motor.set_speed(speed)?;
+        speed = -speed;
+        sleep(Duration::from_secs(2));
+    }
+
+    motor.stop()?;
+
+    Ok(())
+}
+
+// usage: expander [mcp23017|pcf8574]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    match std::env::args().nth(1).as_deref() {
+        None | Some("mcp23017") => mcp23017(&running),
+        Some("pcf8574") => pcf8574(&running),
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+}
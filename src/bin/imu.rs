@@ -0,0 +1,100 @@
+use anyhow::{bail, Context, Result};
+use rpizw_test::devices::mpu6050::{self, Tracker, DEFAULT_ADDR, MPU6050};
+use rppal::gpio::{Gpio, Trigger};
+use rppal::i2c::I2c;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{thread::sleep, time::Duration};
+
+// the MPU6050's INT, for the `interrupt` mode
+const INT_PIN: u8 = 4;
+const CALIBRATION_SAMPLES: usize = 200;
+const ALPHA: f64 = 0.98;
+// well above the 1 g of gravity, a bump or a crash
+const CRASH_G: f64 = 2.5;
+const DELAY: u64 = 100;
+
+fn calibrate(imu: &mut MPU6050<I2c>) -> Result<()> {
+    println!("calibrating, keep still");
+    loop {
+        match imu.calibrate_gyro(CALIBRATION_SAMPLES, Duration::from_millis(5)) {
+            Ok(bias) => {
+                println!("gyro bias: {:?}", bias);
+                return Ok(());
+            }
+            Err(mpu6050::Error::Moved) => println!("moved, again"),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+// usage: imu [filter|interrupt]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mut imu = MPU6050::new(i2c, DEFAULT_ADDR)?;
+    calibrate(&mut imu)?;
+
+    match std::env::args().nth(1).as_deref() {
+        None | Some("filter") => {
+            let tracker = Tracker::spawn(imu, ALPHA, Duration::from_millis(20))?;
+
+            while running.load(Ordering::SeqCst) && !tracker.is_finished() {
+                let o = tracker.orientation();
+                println!(
+                    "roll: {:7.1}, pitch: {:7.1}, yaw: {:7.1}",
+                    o.roll, o.pitch, o.yaw
+                );
+
+                if o.is_upside_down() {
+                    println!("flipped!");
+                }
+                if tracker.sample().accel.magnitude() > CRASH_G {
+                    println!("crash!");
+                }
+
+                sleep(Duration::from_millis(DELAY));
+            }
+
+            tracker.stop()?;
+        }
+        Some("interrupt") => {
+            let mut int = Gpio::new()?.get(INT_PIN)?.into_input();
+            int.set_interrupt(Trigger::RisingEdge)?;
+            imu.enable_data_ready(false)?;
+
+            while running.load(Ordering::SeqCst) {
+                // a timeout means INT isn't wired
+                if int
+                    .poll_interrupt(true, Some(Duration::from_millis(DELAY)))?
+                    .is_none()
+                {
+                    println!("no data ready interrupt");
+                    continue;
+                }
+
+                let sample = imu.read()?;
+                println!(
+                    "accel: {:6.2} {:6.2} {:6.2} g, gyro: {:7.1} {:7.1} {:7.1} deg/s",
+                    sample.accel.x,
+                    sample.accel.y,
+                    sample.accel.z,
+                    sample.gyro.x,
+                    sample.gyro.y,
+                    sample.gyro.z
+                );
+            }
+
+            imu.disable_data_ready()?;
+        }
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use rpizw_test::devices::keypad::{self, KeyEvent, Keypad, KEYMAP_4X4};
+use rpizw_test::devices::motor::{Drive, Motor};
+use rppal::gpio::{Gpio, Mode};
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const DELAY: u64 = 5;
+
+// 4x4 membrane keypad, rows on the first four pins of the connector
+const ROW_PINS: [u8; 4] = [5, 6, 12, 16];
+const COL_PINS: [u8; 4] = [20, 21, 24, 25];
+
+const MOTOR_IN_1: u8 = 27;
+const MOTOR_IN_2: u8 = 17;
+const FREQUENCY: f64 = 120.0;
+const DUTY_CYCLE: f64 = 0.0;
+
+const PIN_CODE: &str = "1234";
+
+// locked until the PIN code and # are entered, then:
+// 0-9: speed, A: forward, B: reverse, *: stop, D: lock again
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    let gpio = Gpio::new()?;
+    let rows = [
+        gpio.get(ROW_PINS[0])?.into_io(Mode::Input),
+        gpio.get(ROW_PINS[1])?.into_io(Mode::Input),
+        gpio.get(ROW_PINS[2])?.into_io(Mode::Input),
+        gpio.get(ROW_PINS[3])?.into_io(Mode::Input),
+    ];
+    let cols = [
+        gpio.get(COL_PINS[0])?.into_input_pullup(),
+        gpio.get(COL_PINS[1])?.into_input_pullup(),
+        gpio.get(COL_PINS[2])?.into_input_pullup(),
+        gpio.get(COL_PINS[3])?.into_input_pullup(),
+    ];
+    let mut keypad = Keypad::new(rows, cols, KEYMAP_4X4, keypad::Config::default())?;
+
+    let in1 = gpio.get(MOTOR_IN_1)?.into_output();
+    let in2 = gpio.get(MOTOR_IN_2)?.into_output();
+    let pwm = Pwm::with_frequency(Channel::Pwm0, FREQUENCY, DUTY_CYCLE, Polarity::Normal, true)?;
+    let mut motor = Motor::l298(in1, in2, pwm)?;
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let mut locked = true;
+    let mut entered = String::new();
+    let mut speed = 0.0;
+    let mut dir = 1.0;
+    let mut was_ghosting = false;
+
+    println!("Locked, enter PIN and #");
+
+    while running.load(Ordering::SeqCst) {
+        keypad.update(Instant::now())?;
+
+        if keypad.is_ghosting() != was_ghosting {
+            was_ghosting = keypad.is_ghosting();
+            if was_ghosting {
+                println!("Too many keys held");
+            }
+        }
+
+        let keys: Vec<char> = keypad
+            .events()
+            .filter_map(|event| match event {
+                KeyEvent::Pressed(key) => Some(key),
+                KeyEvent::Released(_) => None,
+            })
+            .collect();
+
+        for key in keys {
+            if locked {
+                match key {
+                    '#' if entered == PIN_CODE => {
+                        locked = false;
+                        println!("Unlocked");
+                    }
+                    '#' => println!("Wrong PIN"),
+                    '*' => {}
+                    key => {
+                        entered.push(key);
+                        continue;
+                    }
+                }
+                entered.clear();
+                continue;
+            }
+
+            match key {
+                '0'..='9' => speed = f64::from(key.to_digit(10).unwrap_or(0)) / 9.0,
+                'A' => dir = 1.0,
+                'B' => dir = -1.0,
+                '*' => speed = 0.0,
+                'D' => {
+                    locked = true;
+                    speed = 0.0;
+                    println!("Locked");
+                }
+                _ => continue,
+            }
+            motor.set_speed(dir * speed)?;
+        }
+
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    motor.stop()?;
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use embedded_hal::adc::OneShot;
+use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
+use rpizw_test::devices::hd44780::buffered::BufferedLcd;
+use rpizw_test::devices::hd44780::{DEFAULT_ADDR, HD44780};
+use rpizw_test::utils::convert_nb_error;
+use rppal::i2c::I2c;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const ADC_ADDR: u8 = 0x4b;
+const COLS: u8 = 16;
+const ROWS: u8 = 2;
+const SAMPLE: Duration = Duration::from_millis(200);
+// characters sent per loop, about 0.5 ms each at 100 kHz
+const CHARS_PER_UPDATE: usize = 4;
+const DELAY: u64 = 5;
+
+// custom characters 0 to 4 fill 1 to 5 columns, used for the bar graph
+fn bar_chars(lcd: &mut HD44780<I2c>) -> Result<()> {
+    for i in 0..5u8 {
+        let row = 0x1f & !(0x1f >> (i + 1));
+        lcd.create_char(i, [row; 8])?;
+    }
+    Ok(())
+}
+
+fn bar(ratio: f64) -> String {
+    let columns = (ratio.clamp(0.0, 1.0) * f64::from(COLS) * 5.0).round() as u32;
+    let (full, partial) = (columns / 5, columns % 5);
+    let mut bar: String = std::iter::repeat_n('\u{4}', full as usize).collect();
+    if partial > 0 {
+        bar.push(char::from(partial as u8 - 1));
+    }
+    bar
+}
+
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    // the LCD and the ADC share the bus, each gets its own handle
+    let mut adc = ADS7830::new(
+        I2c::new().context("Failed to init I2C")?,
+        ADC_ADDR,
+        Reference::Internal,
+    );
+    let mut ch: Single<CH0> = Single::new();
+
+    let mut lcd = HD44780::new(I2c::new()?, DEFAULT_ADDR, COLS, ROWS)?;
+    bar_chars(&mut lcd)?;
+    let mut lcd = BufferedLcd::new(lcd);
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let mut sampled = Instant::now();
+
+    while running.load(Ordering::SeqCst) {
+        if Instant::now() >= sampled {
+            if let Some(v) = convert_nb_error(adc.read(&mut ch)).context("Cannot read ADC")? {
+                let voltage = v as f64 / 255.0 * 3.3;
+                lcd.clear();
+                write!(lcd, "ADC {:3} {:.2}V", v, voltage)?;
+                lcd.set_line(1, &bar(v as f64 / 255.0));
+            }
+            sampled += SAMPLE;
+        }
+
+        lcd.update(CHARS_PER_UPDATE)?;
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    let mut lcd = lcd.release();
+    lcd.clear()?;
+    lcd.set_backlight(false)?;
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anyhow::{bail, Result};
+use rpizw_test::animation::effects::{Breathe, Chase, Rainbow, Tint};
+use rpizw_test::animation::{Effect, Renderer};
+use rpizw_test::devices::rgb_led::Rgb;
+use rpizw_test::devices::ws2812::{ColorOrder, SPI_FREQUENCY, WS2812};
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const LEDS: usize = 8;
+const BRIGHTNESS: f64 = 0.3;
+const DELAY: u64 = 20;
+
+// usage: led_strip [chase|breathe|rainbow]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let effect: Box<dyn Effect<Rgb>> = match std::env::args().nth(1).as_deref() {
+        None | Some("chase") => Box::new(Tint::new(
+            Chase::new(Duration::from_millis(100), true),
+            Rgb::new(1.0, 0.0, 0.0),
+        )),
+        Some("breathe") => Box::new(Tint::new(
+            Breathe::new(Duration::from_secs(4)),
+            Rgb::from_kelvin(3000.0),
+        )),
+        Some("rainbow") => {
+            let mut rainbow = Rainbow::new(Duration::from_secs(5));
+            rainbow.spread = 1.0 / LEDS as f64;
+            Box::new(rainbow)
+        }
+        Some(other) => bail!("Unknown effect {}", other),
+    };
+
+    let spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, SPI_FREQUENCY, Mode::Mode0)?;
+    let mut strip = WS2812::new(spi, LEDS, ColorOrder::Grb);
+    strip.set_brightness(BRIGHTNESS);
+
+    let mut renderer = Renderer::new(effect, Instant::now());
+
+    while running.load(Ordering::SeqCst) {
+        renderer.render(Instant::now(), strip.pixels_mut());
+        strip.show()?;
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    strip.off()?;
+
+    Ok(())
+}
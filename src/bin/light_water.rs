@@ -1,56 +1,48 @@
 use anyhow::Result;
+use rpizw_test::animation::{effects::Chase, Animator, Digital};
 use rppal::gpio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use switch_hal::{ActiveLow, IntoSwitch, Switch};
 
 const PINS: [u8; 10] = [1, 17, 26, 4, 5, 6, 7, 8, 12, 16];
-const DELAY: u64 = 500;
+const STEP: Duration = Duration::from_millis(500);
+const DELAY: u64 = 10;
 
-fn init_leds() -> Result<Vec<gpio::OutputPin>> {
+// the LEDs are wired to 3.3V, so they light up when the pin is low
+fn init_leds() -> Result<Vec<Digital<Switch<gpio::OutputPin, ActiveLow>>>> {
     let leds: gpio::Result<Vec<gpio::Pin>> = PINS
         .iter()
         .map(|pin| gpio::Gpio::new()?.get(*pin))
         .collect();
 
-    let leds: Vec<gpio::OutputPin> = leds?.into_iter().map(|led| led.into_output()).collect();
+    let leds = leds?
+        .into_iter()
+        .map(|led| Digital(led.into_output().into_active_low_switch()))
+        .collect();
     Ok(leds)
 }
 
-fn leds_off(leds: &mut [gpio::OutputPin]) {
-    for led in leds.iter_mut() {
-        led.set_high();
-    }
-}
-
-fn run(leds: &mut [gpio::OutputPin]) {
-    for led in leds.iter_mut() {
-        led.set_low();
-        sleep(Duration::from_millis(DELAY));
-        led.set_high();
-    }
-    for led in leds.iter_mut().rev() {
-        led.set_low();
-        sleep(Duration::from_millis(DELAY));
-        led.set_high();
-    }
-}
-
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
-    let mut leds = init_leds()?;
-    leds_off(&mut leds);
+    let mut leds = Animator::new(init_leds()?, Chase::new(STEP, true), Instant::now());
+    leds.fill(0.0)?;
 
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })?;
 
     while running.load(Ordering::SeqCst) {
-        run(&mut leds);
+        leds.tick(Instant::now())?;
+        sleep(Duration::from_millis(DELAY));
     }
 
-    leds_off(&mut leds);
+    leds.fill(0.0)?;
 
     Ok(())
 }
@@ -0,0 +1,91 @@
+use anyhow::{bail, Context, Result};
+use embedded_hal::adc::OneShot;
+use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
+use rpizw_test::devices::max7219::matrix::{text_columns, Matrix};
+use rpizw_test::devices::max7219::MAX7219;
+use rpizw_test::utils::convert_nb_error;
+use rppal::i2c::I2c;
+use rppal::spi::{Bus, Mode, SlaveSelect, Spi};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const ADC_ADDR: u8 = 0x4b;
+const SPI_FREQUENCY: u32 = 1_000_000;
+// four 8x8 matrices on the common FC-16 modules
+const MODULES: usize = 4;
+const INTENSITY: u8 = 2;
+const SCROLL_STEP: Duration = Duration::from_millis(50);
+const DELAY: u64 = 10;
+
+fn read_voltage(adc: &mut ADS7830<I2c>, ch: &mut Single<CH0>) -> Result<Option<f64>> {
+    let v = convert_nb_error(adc.read(ch)).context("Cannot read ADC")?;
+    Ok(v.map(|v| v as f64 / 255.0 * 3.3))
+}
+
+// usage: max7219 [matrix|digits]
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let matrix = match std::env::args().nth(1).as_deref() {
+        None | Some("matrix") => true,
+        Some("digits") => false,
+        Some(other) => bail!("Unknown mode {}", other),
+    };
+
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mut adc = ADS7830::new(i2c, ADC_ADDR, Reference::Internal);
+    let mut ch: Single<CH0> = Single::new();
+
+    let spi = Spi::new(Bus::Spi0, SlaveSelect::Ss0, SPI_FREQUENCY, Mode::Mode0)?;
+
+    if matrix {
+        let mut max = MAX7219::new(spi, MODULES)?;
+        max.set_intensity_all(INTENSITY)?;
+        let mut matrix = Matrix::new(max);
+        let mut next_text = Instant::now();
+
+        while running.load(Ordering::SeqCst) {
+            let now = Instant::now();
+
+            // start over with a fresh reading once the text has scrolled by
+            if now >= next_text {
+                if let Some(voltage) = read_voltage(&mut adc, &mut ch)? {
+                    let text = format!("ADC {:.2}V", voltage);
+                    let columns = matrix.width() + text_columns(&text).len();
+                    matrix.scroll_text(&text, SCROLL_STEP, now);
+                    next_text = now + SCROLL_STEP * columns as u32;
+                }
+            }
+
+            matrix.tick(now);
+            matrix.show()?;
+            sleep(Duration::from_millis(DELAY));
+        }
+
+        matrix.release().shutdown_all()?;
+    } else {
+        let mut max = MAX7219::new(spi, 1)?;
+        max.set_intensity(0, INTENSITY)?;
+
+        while running.load(Ordering::SeqCst) {
+            if let Some(voltage) = read_voltage(&mut adc, &mut ch)? {
+                // millivolts
+                max.show_number(0, (voltage * 1000.0).round() as i64)?;
+            }
+            sleep(Duration::from_millis(100));
+        }
+
+        max.shutdown_all()?;
+    }
+
+    Ok(())
+}
@@ -1,62 +1,107 @@
-use anyhow::{Context, Result};
-use embedded_hal::adc::OneShot;
-use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
-use rpizw_test::devices::motor::{Command, Motor};
-use rpizw_test::utils::convert_nb_error;
+use anyhow::{bail, Result};
+use rpizw_test::devices::button::{self, Button, Event};
+use rpizw_test::devices::motor::{Drive, Motor, Standby};
+use rpizw_test::devices::rotary_encoder::{self, Range, RotaryEncoder};
 use rppal::gpio::Gpio;
-use rppal::i2c::I2c;
 use rppal::pwm::{Channel, Polarity, Pwm};
-use std::cmp;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use switch_hal::IntoSwitch;
 
-const ADC_ADDR: u8 = 0x4b;
-const DELAY: u64 = 100;
+// fast enough for the encoder not to miss steps
+const DELAY: u64 = 1;
+
+// KY-040 rotary encoder, clicking SW stops the motor
+const ENCODER_CLK: u8 = 5;
+const ENCODER_DT: u8 = 6;
+const BUTTON_PIN: u8 = 23;
+// detents from stop to full speed
+const SPEED_STEPS: i64 = 20;
 
 const MOTOR_IN_1: u8 = 27;
 const MOTOR_IN_2: u8 = 17;
+// only on a TB6612FNG
+const MOTOR_STBY: u8 = 22;
 const FREQUENCY: f64 = 120.0;
 const DUTY_CYCLE: f64 = 0.0;
 
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
 
-    // knob
-    let i2c = I2c::new().context("Failed to init I2C")?;
-    let mut adc = ADS7830::new(i2c, ADC_ADDR, Reference::Internal);
-    let mut ch: Single<CH0> = Single::new();
-
-    // motor
-    let in1 = Gpio::new()?.get(MOTOR_IN_1)?.into_output();
-    let in2 = Gpio::new()?.get(MOTOR_IN_2)?.into_output();
+    let gpio = Gpio::new()?;
+    let in1 = gpio.get(MOTOR_IN_1)?.into_output();
+    let in2 = gpio.get(MOTOR_IN_2)?.into_output();
     let pwm = Pwm::with_frequency(Channel::Pwm0, FREQUENCY, DUTY_CYCLE, Polarity::Normal, true)?;
 
-    let mut motor = Motor::l298(in1, in2, pwm)?;
+    match std::env::args().nth(1).as_deref() {
+        None | Some("l298") => run(&gpio, &mut Motor::l298(in1, in2, pwm)?, &running),
+        Some("tb6612fng") => {
+            let stby = gpio.get(MOTOR_STBY)?.into_output();
+            let mut standby = Standby::new(stby.into_active_high_switch())?;
+            run(&gpio, &mut Motor::tb6612fng(in1, in2, pwm)?, &running)?;
 
-    ctrlc::set_handler(move || {
-        r.store(false, Ordering::SeqCst);
-    })?;
+            // both channels off, the driver draws next to nothing
+            standby.standby()?;
+            Ok(())
+        }
+        Some(other) => bail!("Unknown mode {}", other),
+    }
+}
+
+fn run<M>(gpio: &Gpio, motor: &mut M, running: &AtomicBool) -> Result<()>
+where
+    M: Drive,
+    M::Error: std::error::Error + Send + Sync + 'static,
+{
+    // knob
+    let mut knob = RotaryEncoder::new(
+        gpio.get(ENCODER_CLK)?.into_input_pullup(),
+        gpio.get(ENCODER_DT)?.into_input_pullup(),
+        rotary_encoder::Config {
+            max_acceleration: 3,
+            ..Default::default()
+        },
+    )?;
+    knob.set_range(Range::Clamped(-SPEED_STEPS, SPEED_STEPS));
+
+    let pin = gpio.get(BUTTON_PIN)?.into_input_pullup();
+    let mut button = Button::new(
+        pin.into_active_low_switch(),
+        button::Config {
+            double_click: Duration::default(),
+            ..Default::default()
+        },
+    );
+
+    let mut speed = 0;
 
     while running.load(Ordering::SeqCst) {
-        match convert_nb_error(adc.read(&mut ch)).context("Cannot read ADC")? {
-            None => println!("Would Block"),
-            Some(v) => {
-                println!("ADC: {}", v);
-                let v = v as i32 - 128;
-                let duty = i32::abs(v) as f64 / 128.0;
-                let cmd = match v.cmp(&0) {
-                    cmp::Ordering::Greater => Command::ClockWise,
-                    cmp::Ordering::Less => Command::CounterClockWise,
-                    cmp::Ordering::Equal => Command::Coast,
-                };
-
-                motor.run(cmd, duty)?;
-                sleep(Duration::from_millis(DELAY));
-            }
+        let now = Instant::now();
+        knob.update(now)?;
+        button.update(now)?;
+
+        if button.events().any(|event| event == Event::Click) {
+            knob.set_value(0);
+        }
+
+        if knob.value() != speed {
+            speed = knob.value();
+            println!("Speed: {}", speed);
+            motor.set_speed(speed as f64 / SPEED_STEPS as f64)?;
         }
+
+        sleep(Duration::from_millis(DELAY));
     }
 
+    motor.stop()?;
+
     Ok(())
 }
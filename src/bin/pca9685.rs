@@ -0,0 +1,53 @@
+use anyhow::{Context, Result};
+use rpizw_test::devices::pca9685::{PwmChannel, DEFAULT_ADDR, PCA9685};
+use rpizw_test::devices::servo::motion::{Easing, ServoMotion};
+use rpizw_test::devices::servo::{self, Servo};
+use rpizw_test::utils::lock;
+use rppal::i2c::I2c;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+// servos want 50 Hz, LEDs don't mind
+const FREQUENCY: f64 = 50.0;
+const SERVO_CHANNEL: u8 = 0;
+const SERVO_SPEED: f64 = 90.0;
+const DELAY: u64 = 20;
+
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mut pca = PCA9685::new(i2c, DEFAULT_ADDR).context("Failed to init PCA9685")?;
+    let frequency = pca.set_frequency(FREQUENCY)?;
+    println!("PWM frequency: {} Hz", frequency);
+
+    let pca = pca.into_shared();
+    let config = servo::Config {
+        period: Duration::from_secs_f64(1.0 / frequency),
+        ..Default::default()
+    };
+    let servo = Servo::new(PwmChannel::new(&pca, SERVO_CHANNEL), config)?;
+    let mut servo = ServoMotion::new(servo, SERVO_SPEED, Easing::EaseInOut);
+
+    servo.sweep(0.0, 180.0, Instant::now())?;
+
+    while running.load(Ordering::SeqCst) {
+        servo.tick(Instant::now());
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    servo.servo_mut().center();
+    sleep(Duration::from_millis(DELAY));
+    lock(&pca).sleep()?;
+
+    Ok(())
+}
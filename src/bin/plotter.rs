@@ -0,0 +1,106 @@
+use anyhow::{Context, Result};
+use rpizw_test::devices::servo::{self, InvalidAngle, Servo};
+use rpizw_test::devices::stepper_motor::{planner::Planner, Dir, StepperMotor};
+use rpizw_test::gcode::machine::Machine;
+use rppal::gpio::Gpio;
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use switch_hal::OutputSwitch;
+
+const X_PINS: [u8; 4] = [18, 23, 24, 25];
+const Y_PINS: [u8; 4] = [5, 6, 13, 19];
+const DELAY: Duration = Duration::from_millis(3);
+
+// 28BYJ-48: 2048 steps per revolution on a 40 mm per revolution pulley
+const STEPS_PER_MM: [f64; 2] = [51.2, 51.2];
+const FEED: f64 = 300.0;
+const RAPID: f64 = 1200.0;
+
+// pen lift servo
+const SERVO_PERIOD: Duration = Duration::from_millis(20);
+const PEN_UP: f64 = 90.0;
+const PEN_DOWN: f64 = 45.0;
+
+struct Pen {
+    servo: Servo<Pwm>,
+}
+
+impl OutputSwitch for Pen {
+    type Error = InvalidAngle;
+
+    fn on(&mut self) -> Result<(), Self::Error> {
+        self.servo.set_angle(PEN_DOWN)
+    }
+
+    fn off(&mut self) -> Result<(), Self::Error> {
+        self.servo.set_angle(PEN_UP)
+    }
+}
+
+fn init_pins(pins: &[u8]) -> Result<Vec<rppal::gpio::OutputPin>> {
+    let gpio = Gpio::new()?;
+    let pins = pins
+        .iter()
+        .map(|pin| Ok(gpio.get(*pin)?.into_output()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(pins)
+}
+
+// usage: plotter [FILE], reads G-code from stdin when no file is given
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let input: Box<dyn BufRead> = match std::env::args().nth(1) {
+        Some(path) => Box::new(BufReader::new(
+            File::open(&path).with_context(|| format!("Cannot open {}", path))?,
+        )),
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let mut x_pins = init_pins(&X_PINS)?;
+    let mut y_pins = init_pins(&Y_PINS)?;
+    let x = StepperMotor::new(&mut x_pins, 0, Dir::CW, DELAY)?;
+    let y = StepperMotor::new(&mut y_pins, 0, Dir::CW, DELAY)?;
+
+    let config = servo::Config {
+        period: SERVO_PERIOD,
+        ..Default::default()
+    };
+    let pwm = Pwm::with_period(
+        Channel::Pwm0,
+        SERVO_PERIOD,
+        config.pulse_width(PEN_UP),
+        Polarity::Normal,
+        true,
+    )?;
+    let mut pen = Pen {
+        servo: Servo::new(pwm, config)?,
+    };
+    pen.off()?;
+
+    let mut machine = Machine::new(Planner::new(vec![x, y]), pen, &STEPS_PER_MM, FEED, RAPID);
+
+    for (n, line) in input.lines().enumerate() {
+        if !running.load(Ordering::SeqCst) {
+            break;
+        }
+
+        let line = line?;
+        machine
+            .execute_line(&line)
+            .with_context(|| format!("line {}: {}", n + 1, line))?;
+    }
+
+    machine.tool_mut().off()?;
+
+    Ok(())
+}
@@ -1,65 +1,47 @@
 use anyhow::Result;
+use rpizw_test::animation::{effects::RandomColor, Animator};
+use rpizw_test::devices::rgb_led::{self, Rgb, RgbLed};
+use rpizw_test::utils::soft_pwm::SoftPwm;
 use rppal::gpio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
-
-struct ColorLED {
-    r: gpio::OutputPin,
-    g: gpio::OutputPin,
-    b: gpio::OutputPin,
-    frequency: f64,
-}
-
-fn init_soft_pwm(pin: u8, frequency: f64) -> Result<gpio::OutputPin> {
-    let mut pwm = gpio::Gpio::new()?.get(pin)?.into_output();
-    pwm.set_pwm_frequency(frequency, 0.0)?;
-    Ok(pwm)
-}
-
-impl ColorLED {
-    fn new(r: u8, g: u8, b: u8, frequency: f64) -> Result<ColorLED> {
-        Ok(ColorLED {
-            r: init_soft_pwm(r, frequency)?,
-            g: init_soft_pwm(g, frequency)?,
-            b: init_soft_pwm(b, frequency)?,
-            frequency,
-        })
-    }
-
-    fn set_rgb(&mut self, r: f64, g: f64, b: f64) -> Result<()> {
-        self.r.set_pwm_frequency(self.frequency, r)?;
-        self.g.set_pwm_frequency(self.frequency, g)?;
-        self.b.set_pwm_frequency(self.frequency, b)?;
-
-        Ok(())
-    }
-}
-
-fn run(led: &mut ColorLED) -> Result<()> {
-    let r: f64 = rand::random();
-    let g: f64 = rand::random();
-    let b: f64 = rand::random();
-
-    led.set_rgb(r, g, b)?;
-
-    sleep(Duration::from_millis(1000));
-
-    Ok(())
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const PINS: [u8; 3] = [16, 20, 21];
+const FREQUENCY: f64 = 100.0;
+const INTERVAL: Duration = Duration::from_millis(1500);
+const FADE: Duration = Duration::from_millis(500);
+const DELAY: u64 = 10;
+
+fn init_soft_pwm(pin: u8) -> Result<SoftPwm> {
+    let pin = gpio::Gpio::new()?.get(pin)?.into_output();
+    Ok(SoftPwm::new(pin, FREQUENCY))
 }
 
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
-    let mut led = ColorLED::new(16, 20, 21, 100.0)?;
+    let led = RgbLed::new(
+        init_soft_pwm(PINS[0])?,
+        init_soft_pwm(PINS[1])?,
+        init_soft_pwm(PINS[2])?,
+        rgb_led::Config::default(),
+    );
+    let mut animator = Animator::new(vec![led], RandomColor::new(INTERVAL, FADE), Instant::now());
 
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })?;
 
     while running.load(Ordering::SeqCst) {
-        run(&mut led)?;
+        animator.tick(Instant::now())?;
+        sleep(Duration::from_millis(DELAY));
     }
 
+    animator.fill(Rgb::BLACK)?;
+
     Ok(())
 }
@@ -0,0 +1,51 @@
+use anyhow::Result;
+use rpizw_test::devices::servo::motion::{Easing, ServoMotion};
+use rpizw_test::devices::servo::{self, Servo};
+use rppal::pwm::{Channel, Polarity, Pwm};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const SERVO_PERIOD: Duration = Duration::from_millis(20);
+const SWEEP_MIN: f64 = 0.0;
+const SWEEP_MAX: f64 = 180.0;
+// degrees per second
+const SPEED: f64 = 90.0;
+const DELAY: u64 = 15;
+
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let config = servo::Config {
+        period: SERVO_PERIOD,
+        ..Default::default()
+    };
+    let pwm = Pwm::with_period(
+        Channel::Pwm0,
+        SERVO_PERIOD,
+        config.pulse_width(config.center()),
+        Polarity::Normal,
+        true,
+    )?;
+    let mut servo = ServoMotion::new(Servo::new(pwm, config)?, SPEED, Easing::EaseInOut);
+
+    servo.sweep(SWEEP_MIN, SWEEP_MAX, Instant::now())?;
+
+    while running.load(Ordering::SeqCst) {
+        servo.tick(Instant::now());
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    servo.servo_mut().center();
+    sleep(Duration::from_millis(DELAY));
+
+    Ok(())
+}
@@ -0,0 +1,84 @@
+use anyhow::{Context, Result};
+use embedded_hal::adc::OneShot;
+use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
+use rpizw_test::devices::hc595::{OutputBit, HC595};
+use rpizw_test::devices::seven_segment::SevenSegment;
+use rpizw_test::utils::{convert_nb_error, lock};
+use rppal::{gpio::Gpio, i2c::I2c};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{
+    array,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use switch_hal::IntoSwitch;
+
+const DATA_PIN: u8 = 17;
+const CLOCK_PIN: u8 = 27;
+const LATCH_PIN: u8 = 22;
+const ADC_ADDR: u8 = 0x4b;
+
+// chip 0 drives the segments, chip 1 the digits and chip 2 an 8 LED bar graph
+const SEGMENTS: usize = 0;
+const DIGITS: usize = 1;
+const BAR: usize = 2;
+
+const SAMPLE: Duration = Duration::from_millis(100);
+const DELAY: u64 = 2;
+
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    let gpio = Gpio::new()?;
+    let sr = HC595::<_, _, _, _, 3>::new(
+        gpio.get(DATA_PIN)?.into_output(),
+        gpio.get(CLOCK_PIN)?.into_output(),
+        gpio.get(LATCH_PIN)?.into_output(),
+    )?
+    .into_shared();
+
+    // common cathode: a segment lights when high, its digit when low
+    let mut segments: [_; 8] =
+        array::from_fn(|i| OutputBit::new(&sr, SEGMENTS * 8 + i).into_active_high_switch());
+    let mut digits: [_; 4] =
+        array::from_fn(|i| OutputBit::new(&sr, DIGITS * 8 + i).into_active_low_switch());
+    let mut display: SevenSegment<4> = SevenSegment::new();
+
+    let i2c = I2c::new().context("Failed to init I2C")?;
+    let mut adc = ADS7830::new(i2c, ADC_ADDR, Reference::Internal);
+    let mut ch: Single<CH0> = Single::new();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    display.scroll_text("HELLO", Duration::from_millis(300), Instant::now());
+    let mut sampled = Instant::now() + Duration::from_secs(3);
+
+    while running.load(Ordering::SeqCst) {
+        let now = Instant::now();
+        display.tick(now);
+
+        if now >= sampled {
+            if let Some(v) = convert_nb_error(adc.read(&mut ch)).context("Cannot read ADC")? {
+                display.show_number(v.into());
+                // light one more LED every 32 counts
+                let lit = (u32::from(v) + 16) / 32;
+                // written out with the next refresh
+                lock(&sr).set_byte(BAR, ((1u16 << lit) - 1) as u8);
+            }
+            sampled = now + SAMPLE;
+        }
+
+        display.refresh(&mut segments, &mut digits)?;
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    let mut sr = lock(&sr);
+    sr.set_bytes([0; 3]);
+    sr.write()?;
+
+    Ok(())
+}
@@ -1,41 +1,114 @@
-use anyhow::{Context, Result};
-use embedded_hal::adc::OneShot;
-use rpizw_test::devices::ads7830::{Reference, Single, ADS7830, CH0};
-use rpizw_test::utils::convert_nb_error;
-use rppal::i2c::I2c;
+use anyhow::Result;
+use rpizw_test::animation::{effects::Breathe, Effect};
+use rpizw_test::devices::button::{self, Button, Event};
+use rpizw_test::devices::rotary_encoder::{self, Range, RotaryEncoder};
+use rppal::gpio::Gpio;
 use rppal::pwm::{Channel, Polarity, Pwm};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use std::{thread::sleep, time::Duration};
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+use switch_hal::IntoSwitch;
 
-const ADC_ADDR: u8 = 0x4b;
-const DELAY: u64 = 30;
+// fast enough for the encoder not to miss steps
+const DELAY: u64 = 1;
 const FREQUENCY: f64 = 100.0;
+// KY-040 rotary encoder, SW is the mode button
+const ENCODER_CLK: u8 = 5;
+const ENCODER_DT: u8 = 6;
+const BUTTON_PIN: u8 = 23;
+const BRIGHTNESS_STEPS: i64 = 50;
+const BREATHE_PERIOD: Duration = Duration::from_secs(4);
 
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Mode {
+    /// brightness follows the encoder
+    Knob,
+    Breathe,
+    Full,
+    Off,
+}
+
+impl Mode {
+    fn next(self) -> Self {
+        match self {
+            Mode::Knob => Mode::Breathe,
+            Mode::Breathe => Mode::Full,
+            Mode::Full | Mode::Off => Mode::Knob,
+        }
+    }
+}
+
+// click: next mode, double click: full brightness, long press: off
 fn main() -> Result<()> {
     let running = Arc::new(AtomicBool::new(true));
     let r = running.clone();
 
     let led = Pwm::with_frequency(Channel::Pwm0, FREQUENCY, 0.0, Polarity::Normal, true)?;
-    let i2c = I2c::new().context("Failed to init I2C")?;
-    let mut adc = ADS7830::new(i2c, ADC_ADDR, Reference::Internal);
-    let mut ch: Single<CH0> = Single::new();
+    let gpio = Gpio::new()?;
+    let mut encoder = RotaryEncoder::new(
+        gpio.get(ENCODER_CLK)?.into_input_pullup(),
+        gpio.get(ENCODER_DT)?.into_input_pullup(),
+        rotary_encoder::Config {
+            max_acceleration: 5,
+            ..Default::default()
+        },
+    )?;
+    encoder.set_range(Range::Clamped(0, BRIGHTNESS_STEPS));
+    encoder.set_value(BRIGHTNESS_STEPS / 2);
+
+    let pin = gpio.get(BUTTON_PIN)?.into_input_pullup();
+    let mut button = Button::new(pin.into_active_low_switch(), button::Config::default());
 
     ctrlc::set_handler(move || {
         r.store(false, Ordering::SeqCst);
     })?;
 
+    let mut mode = Mode::Knob;
+    let mut breathe = Breathe::new(BREATHE_PERIOD);
+    let mut mode_started = Instant::now();
+    let mut last_level = -1.0;
+
     while running.load(Ordering::SeqCst) {
-        match convert_nb_error(adc.read(&mut ch)).context("Cannot read ADC")? {
-            None => println!("Would Block"),
-            Some(v) => {
-                println!("ADC Value {}", v);
-
-                led.set_duty_cycle(v as f64 / 255.0)?;
-                sleep(Duration::from_millis(DELAY));
-            }
+        let now = Instant::now();
+        button.update(now)?;
+
+        let previous = mode;
+        // turning the knob takes over from any other mode
+        if encoder.update(now)? != 0 {
+            mode = Mode::Knob;
+        }
+        for event in button.events() {
+            mode = match event {
+                Event::Click => mode.next(),
+                Event::DoubleClick => Mode::Full,
+                Event::LongPress => Mode::Off,
+                _ => mode,
+            };
         }
+        if mode != previous {
+            println!("Mode {:?}", mode);
+            mode_started = now;
+        }
+
+        let level = match mode {
+            Mode::Knob => encoder.ratio(),
+            Mode::Breathe => breathe.value(now - mode_started, 0, 1),
+            Mode::Full => 1.0,
+            Mode::Off => 0.0,
+        };
+        // the PWM is set through sysfs, skip writes that change nothing
+        if level != last_level {
+            led.set_duty_cycle(level)?;
+            last_level = level;
+        }
+
+        sleep(Duration::from_millis(DELAY));
     }
 
+    led.set_duty_cycle(0.0)?;
+
     Ok(())
 }
@@ -1,16 +1,22 @@
-use rpizw_test::devices::stepper_motor::{Dir, StepperMotor};
+use rpizw_test::devices::stepper_motor::{owned::OwnedStepperMotor, Dir};
 use rppal::gpio::Gpio;
 use std::{
+    convert::TryInto,
     result::Result,
     sync::{
         atomic::{AtomicBool, Ordering},
         Arc,
     },
-    time::Duration,
+    thread::sleep,
+    time::{Duration, Instant},
 };
 
 const PINS: [u8; 4] = [18, 23, 24, 25];
 const DELAY: Duration = Duration::from_millis(3);
+// turn for a while, then rest long enough for the coils to be released
+const TURN: Duration = Duration::from_secs(3);
+const REST: Duration = Duration::from_secs(2);
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
 
 fn main() -> anyhow::Result<()> {
     let running = Arc::new(AtomicBool::new(true));
@@ -20,19 +26,38 @@ fn main() -> anyhow::Result<()> {
     })?;
 
     // initialize
-    let mut pins = PINS
+    let pins: [_; 4] = PINS
         .iter()
         .map(|pin| Gpio::new()?.get(*pin))
         .collect::<Result<Vec<_>, _>>()?
         .into_iter()
         .map(|pin| pin.into_output())
-        .collect::<Vec<_>>();
+        .collect::<Vec<_>>()
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Expected {} pins", PINS.len()))?;
 
-    let mut stepper = StepperMotor::new(&mut pins, 0, Dir::CCW, DELAY)?;
+    let mut stepper = OwnedStepperMotor::new(pins, 0, Dir::CCW, DELAY)?;
+    stepper.set_idle_timeout(Some(IDLE_TIMEOUT));
 
+    let mut phase = Instant::now();
     while running.load(Ordering::SeqCst) {
-        stepper.step()?;
+        let elapsed = phase.elapsed();
+        if elapsed < TURN {
+            stepper.step()?;
+        } else if elapsed < TURN + REST {
+            let released = stepper.is_released();
+            stepper.poll()?;
+            if !released && stepper.is_released() {
+                println!("idle, coils released");
+            }
+            sleep(Duration::from_millis(10));
+        } else {
+            phase = Instant::now();
+        }
     }
 
+    // don't leave a coil energised
+    stepper.release()?;
+
     Ok(())
 }
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use rpizw_test::devices::dht::{Dht, Model};
+use rpizw_test::devices::ds18b20;
+use rppal::gpio::{Gpio, Mode};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::{thread::sleep, time::Duration};
+
+// GPIO 4 is taken by the 1-Wire bus
+const DHT_PIN: u8 = 17;
+const DHT_MODEL: Model = Model::Dht22;
+const DHT_ATTEMPTS: usize = 3;
+const DELAY: u64 = 5000;
+
+fn main() -> Result<()> {
+    let running = Arc::new(AtomicBool::new(true));
+    let r = running.clone();
+
+    ctrlc::set_handler(move || {
+        r.store(false, Ordering::SeqCst);
+    })?;
+
+    let mut dht = Dht::new(Gpio::new()?.get(DHT_PIN)?.into_io(Mode::Input), DHT_MODEL);
+
+    // no bus, no probes
+    let probes = ds18b20::discover().unwrap_or_default();
+    for probe in &probes {
+        println!("found probe {}", probe.rom());
+    }
+
+    // a sensor failing is logged, the others keep going
+    while running.load(Ordering::SeqCst) {
+        match dht.read_retry(DHT_ATTEMPTS) {
+            Ok(reading) => println!(
+                "cabin: {}, humidity: {}",
+                reading.temperature, reading.humidity
+            ),
+            Err(e) => println!("cabin: {}", e),
+        }
+
+        for probe in &probes {
+            match probe.read() {
+                Ok(temperature) => println!("{}: {}", probe.rom(), temperature),
+                Err(e) => println!("{}: {}", probe.rom(), e),
+            }
+        }
+
+        sleep(Duration::from_millis(DELAY));
+    }
+
+    Ok(())
+}
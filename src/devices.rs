@@ -1,3 +1,23 @@
 pub mod ads7830;
+pub mod button;
+pub mod buzzer;
+pub mod dac;
+pub mod dht;
+pub mod ds18b20;
+pub mod expander;
+pub mod hc595;
+pub mod hcsr04;
+pub mod hd44780;
+pub mod ir;
+pub mod keypad;
+pub mod max7219;
 pub mod motor;
+pub mod mpu6050;
+pub mod pca9685;
+pub mod rgb_led;
+pub mod rotary_encoder;
+pub mod servo;
+pub mod seven_segment;
+pub mod ssd1306;
 pub mod stepper_motor;
+pub mod ws2812;
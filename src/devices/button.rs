@@ -0,0 +1,307 @@
+//! Debounced push button with click, double-click and long-press detection
+
+use std::collections::VecDeque;
+use std::sync::mpsc::Sender;
+use std::thread::{self, sleep, JoinHandle};
+use std::time::{Duration, Instant};
+use switch_hal::InputSwitch;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Event {
+    Press,
+    Release,
+    /// A short press with no second one within the double-click window
+    Click,
+    DoubleClick,
+    /// Held for the long-press time, no click follows the release
+    LongPress,
+    /// Repeats every repeat interval while held after a long press
+    Repeat,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// How long the input must be stable before a change counts
+    pub debounce: Duration,
+    /// Longest gap between two clicks of a double-click, zero reports
+    /// every click right away
+    pub double_click: Duration,
+    pub long_press: Duration,
+    /// `None` disables repeat events
+    pub repeat: Option<Duration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(20),
+            double_click: Duration::from_millis(300),
+            long_press: Duration::from_millis(800),
+            repeat: None,
+        }
+    }
+}
+
+/// A button read through a switch, which also sets the polarity
+///
+/// e.g. `pin.into_input_pullup().into_active_low_switch()` for a button to
+/// ground. Call [`update`](Self::update) every few milliseconds, then take
+/// the [`events`](Self::events), or [`spawn`](Self::spawn) a thread that
+/// sends them through a channel.
+pub struct Button<SW> {
+    switch: SW,
+    config: Config,
+    /// Debounced state
+    pressed: bool,
+    /// Raw state and when it last changed
+    raw: bool,
+    raw_since: Instant,
+    pressed_at: Instant,
+    long_pressed: bool,
+    next_repeat: Option<Instant>,
+    clicks: u8,
+    click_deadline: Instant,
+    events: VecDeque<Event>,
+    callback: Option<Box<dyn FnMut(Event) + Send>>,
+}
+
+impl<SW, E> Button<SW>
+where
+    SW: InputSwitch<Error = E>,
+{
+    /// Creates a new `Button`, the current state is taken as not pressed
+    pub fn new(switch: SW, config: Config) -> Self {
+        let now = Instant::now();
+
+        Self {
+            switch,
+            config,
+            pressed: false,
+            raw: false,
+            raw_since: now,
+            pressed_at: now,
+            long_pressed: false,
+            next_repeat: None,
+            clicks: 0,
+            click_deadline: now,
+            events: VecDeque::new(),
+            callback: None,
+        }
+    }
+
+    /// Calls `callback` for each event instead of queueing it
+    pub fn set_callback<F>(&mut self, callback: F)
+    where
+        F: FnMut(Event) + Send + 'static,
+    {
+        self.callback = Some(Box::new(callback));
+    }
+
+    /// The debounced state
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Reads the switch and generates events
+    pub fn update(&mut self, now: Instant) -> Result<(), E> {
+        let raw = self.switch.is_active()?;
+        if raw != self.raw {
+            self.raw = raw;
+            self.raw_since = now;
+        }
+
+        let stable = now.saturating_duration_since(self.raw_since) >= self.config.debounce;
+        if stable && self.raw != self.pressed {
+            self.pressed = self.raw;
+            if self.pressed {
+                self.on_press(now);
+            } else {
+                self.on_release(now);
+            }
+        }
+
+        if self.pressed {
+            self.while_held(now);
+        } else if self.clicks == 1 && now >= self.click_deadline {
+            self.clicks = 0;
+            self.emit(Event::Click);
+        }
+
+        Ok(())
+    }
+
+    /// Takes the queued events
+    pub fn events(&mut self) -> impl Iterator<Item = Event> + '_ {
+        self.events.drain(..)
+    }
+
+    pub fn release(self) -> SW {
+        self.switch
+    }
+
+    fn on_press(&mut self, now: Instant) {
+        self.pressed_at = now;
+        self.long_pressed = false;
+        self.next_repeat = None;
+        self.emit(Event::Press);
+    }
+
+    fn on_release(&mut self, now: Instant) {
+        self.emit(Event::Release);
+
+        if self.long_pressed {
+            self.clicks = 0;
+            return;
+        }
+
+        self.clicks += 1;
+        if self.clicks == 2 {
+            self.clicks = 0;
+            self.emit(Event::DoubleClick);
+        } else if self.config.double_click == Duration::default() {
+            self.clicks = 0;
+            self.emit(Event::Click);
+        } else {
+            self.click_deadline = now + self.config.double_click;
+        }
+    }
+
+    fn while_held(&mut self, now: Instant) {
+        if !self.long_pressed {
+            if now.saturating_duration_since(self.pressed_at) >= self.config.long_press {
+                self.long_pressed = true;
+                // a pending first click is swallowed by the long press
+                self.clicks = 0;
+                self.next_repeat = self.config.repeat.map(|interval| now + interval);
+                self.emit(Event::LongPress);
+            }
+            return;
+        }
+
+        if let (Some(at), Some(interval)) = (self.next_repeat, self.config.repeat) {
+            if now >= at {
+                self.next_repeat = Some(at + interval);
+                self.emit(Event::Repeat);
+            }
+        }
+    }
+
+    fn emit(&mut self, event: Event) {
+        match self.callback.as_mut() {
+            Some(callback) => callback(event),
+            None => self.events.push_back(event),
+        }
+    }
+}
+
+impl<SW, E> Button<SW>
+where
+    SW: InputSwitch<Error = E> + Send + 'static,
+    E: Send + 'static,
+{
+    /// Polls the button every `interval` on a new thread, sending events to
+    /// `sender`
+    ///
+    /// The thread stops when the receiver is dropped or reading fails. With
+    /// a [callback](Self::set_callback) set the events go to it instead,
+    /// nothing is sent and the thread only stops when reading fails.
+    pub fn spawn(mut self, interval: Duration, sender: Sender<Event>) -> JoinHandle<Result<(), E>> {
+        thread::spawn(move || loop {
+            self.update(Instant::now())?;
+            for event in self.events() {
+                if sender.send(event).is_err() {
+                    return Ok(());
+                }
+            }
+            sleep(interval);
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::convert::Infallible;
+
+    struct Switch(bool);
+
+    impl InputSwitch for Switch {
+        type Error = Infallible;
+
+        fn is_active(&self) -> Result<bool, Infallible> {
+            Ok(self.0)
+        }
+    }
+
+    /// Holds each `(pressed, ms)` step, updating every millisecond
+    fn run(config: Config, steps: &[(bool, u64)]) -> Vec<Event> {
+        let mut button = Button::new(Switch(false), config);
+        let mut now = Instant::now();
+        let mut events = Vec::new();
+
+        for &(pressed, ms) in steps {
+            button.switch.0 = pressed;
+            for _ in 0..ms {
+                button.update(now).unwrap();
+                events.extend(button.events());
+                now += Duration::from_millis(1);
+            }
+        }
+
+        events
+    }
+
+    #[test]
+    fn debounces() {
+        let bounces = [(true, 5), (false, 5), (true, 5), (false, 500)];
+        assert_eq!(run(Config::default(), &bounces), vec![]);
+
+        let press = [(true, 5), (false, 5), (true, 50), (false, 500)];
+        assert_eq!(
+            run(Config::default(), &press),
+            vec![Event::Press, Event::Release, Event::Click]
+        );
+    }
+
+    #[test]
+    fn single_and_double_click() {
+        let single = [(true, 50), (false, 500)];
+        assert_eq!(
+            run(Config::default(), &single),
+            vec![Event::Press, Event::Release, Event::Click]
+        );
+
+        let double = [(true, 50), (false, 100), (true, 50), (false, 500)];
+        assert_eq!(
+            run(Config::default(), &double),
+            vec![
+                Event::Press,
+                Event::Release,
+                Event::Press,
+                Event::Release,
+                Event::DoubleClick
+            ]
+        );
+    }
+
+    #[test]
+    fn long_press_repeats() {
+        let config = Config {
+            repeat: Some(Duration::from_millis(100)),
+            ..Config::default()
+        };
+
+        // pressed at 20 ms, long press at 820, repeats at 920 and 1020,
+        // released at 1120
+        assert_eq!(
+            run(config, &[(true, 1100), (false, 500)]),
+            vec![
+                Event::Press,
+                Event::LongPress,
+                Event::Repeat,
+                Event::Repeat,
+                Event::Release
+            ]
+        );
+    }
+}
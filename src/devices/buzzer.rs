@@ -0,0 +1,230 @@
+//! Passive piezo buzzer driven by a square wave
+//!
+//! [`Buzzer`] plays a queue of [`Note`]s without blocking: start a melody
+//! with [`play`](Buzzer::play) and call [`tick`](Buzzer::tick) from the
+//! control loop. Melodies can be written in RTTTL, see [`rtttl`].
+
+use crate::utils::soft_pwm::SoftPwm;
+use rppal::{gpio, pwm};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+pub mod rtttl;
+
+/// Silence at the end of every note, so repeated notes are heard apart
+const GAP: Duration = Duration::from_millis(10);
+
+/// Something that can output a square wave at a given frequency
+pub trait ToneOutput {
+    type Error;
+
+    /// Plays `frequency` in Hz, `None` silences
+    fn set_tone(&mut self, frequency: Option<f64>) -> Result<(), Self::Error>;
+}
+
+impl ToneOutput for pwm::Pwm {
+    type Error = pwm::Error;
+
+    fn set_tone(&mut self, frequency: Option<f64>) -> Result<(), pwm::Error> {
+        match frequency {
+            Some(frequency) => {
+                self.set_frequency(frequency, 0.5)?;
+                self.enable()
+            }
+            None => self.disable(),
+        }
+    }
+}
+
+impl ToneOutput for SoftPwm {
+    type Error = gpio::Error;
+
+    fn set_tone(&mut self, frequency: Option<f64>) -> Result<(), gpio::Error> {
+        match frequency {
+            Some(frequency) => {
+                self.set_frequency(frequency)?;
+                embedded_hal::PwmPin::set_duty(self, 0.5);
+                embedded_hal::PwmPin::enable(self);
+            }
+            None => embedded_hal::PwmPin::disable(self),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Note {
+    /// In Hz, `None` for a rest
+    pub frequency: Option<f64>,
+    pub duration: Duration,
+}
+
+impl Note {
+    pub fn tone(frequency: f64, duration: Duration) -> Self {
+        Self {
+            frequency: Some(frequency),
+            duration,
+        }
+    }
+
+    pub fn rest(duration: Duration) -> Self {
+        Self {
+            frequency: None,
+            duration,
+        }
+    }
+}
+
+/// Two rising tones
+pub fn startup_chirp() -> Vec<Note> {
+    vec![
+        Note::tone(1319.0, Duration::from_millis(80)),
+        Note::tone(1976.0, Duration::from_millis(120)),
+    ]
+}
+
+/// Three short low beeps
+pub fn low_battery() -> Vec<Note> {
+    let beep = [
+        Note::tone(440.0, Duration::from_millis(100)),
+        Note::rest(Duration::from_millis(100)),
+    ];
+    beep.iter().cycle().take(6).copied().collect()
+}
+
+/// A truck's reverse beeper, meant to be [repeated](Buzzer::play_repeat)
+pub fn reverse_beeper() -> Vec<Note> {
+    vec![
+        Note::tone(1000.0, Duration::from_millis(500)),
+        Note::rest(Duration::from_millis(500)),
+    ]
+}
+
+enum Phase {
+    Tone,
+    Gap,
+}
+
+pub struct Buzzer<T> {
+    output: T,
+    queue: VecDeque<Note>,
+    /// Played again once the queue runs out
+    repeat: Option<Vec<Note>>,
+    phase: Phase,
+    /// When the current phase ends, `None` while idle
+    until: Option<Instant>,
+}
+
+impl<T, E> Buzzer<T>
+where
+    T: ToneOutput<Error = E>,
+{
+    /// Creates a new `Buzzer`, silent
+    pub fn new(mut output: T) -> Result<Self, E> {
+        output.set_tone(None)?;
+
+        Ok(Self {
+            output,
+            queue: VecDeque::new(),
+            repeat: None,
+            phase: Phase::Tone,
+            until: None,
+        })
+    }
+
+    /// Plays `frequency` until told otherwise, stopping any melody
+    pub fn tone(&mut self, frequency: f64) -> Result<(), E> {
+        self.clear();
+        self.output.set_tone(Some(frequency))
+    }
+
+    /// Replaces whatever is playing with `notes`
+    pub fn play(&mut self, notes: Vec<Note>, now: Instant) -> Result<(), E> {
+        self.clear();
+        self.queue.extend(notes);
+        self.next_note(now)
+    }
+
+    /// Plays `notes` over and over until [`stop`](Self::stop)
+    pub fn play_repeat(&mut self, notes: Vec<Note>, now: Instant) -> Result<(), E> {
+        self.play(notes.clone(), now)?;
+        self.repeat = Some(notes);
+        Ok(())
+    }
+
+    /// Plays `notes` after the current melody, a repeating melody stops
+    /// repeating
+    pub fn queue(&mut self, notes: Vec<Note>, now: Instant) -> Result<(), E> {
+        self.repeat = None;
+        self.queue.extend(notes);
+        if self.until.is_none() {
+            self.next_note(now)?;
+        }
+        Ok(())
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.until.is_some()
+    }
+
+    pub fn stop(&mut self) -> Result<(), E> {
+        self.clear();
+        self.output.set_tone(None)
+    }
+
+    /// Moves the melody on, call this at least every few milliseconds
+    pub fn tick(&mut self, now: Instant) -> Result<(), E> {
+        while let Some(until) = self.until {
+            if now < until {
+                break;
+            }
+
+            match self.phase {
+                Phase::Tone => {
+                    self.output.set_tone(None)?;
+                    self.phase = Phase::Gap;
+                    self.until = Some(until + GAP);
+                }
+                // catching up from `until` keeps a slow loop in time
+                Phase::Gap => self.next_note(until)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn release(mut self) -> Result<T, E> {
+        self.stop()?;
+        Ok(self.output)
+    }
+
+    fn clear(&mut self) {
+        self.queue.clear();
+        self.repeat = None;
+        self.until = None;
+    }
+
+    /// Starts the next note at `at`, or goes idle
+    fn next_note(&mut self, at: Instant) -> Result<(), E> {
+        if self.queue.is_empty() {
+            if let Some(repeat) = &self.repeat {
+                self.queue.extend(repeat.iter().copied());
+            }
+        }
+
+        match self.queue.pop_front() {
+            Some(note) => {
+                self.output.set_tone(note.frequency)?;
+                self.phase = Phase::Tone;
+                self.until = Some(at + note.duration.saturating_sub(GAP));
+            }
+            None => {
+                self.output.set_tone(None)?;
+                self.until = None;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+//! RTTTL, the Nokia ringtone format
+//!
+//! `name:d=4,o=5,b=120:8c6,8p,e.,16g#4` is a name, the defaults for
+//! duration, octave and beats per minute, then the notes. A note is an
+//! optional duration (1, 2, 4, 8, 16 or 32, a fraction of a whole note), a
+//! letter (`p` is a pause), an optional `#`, an optional octave and an
+//! optional `.` making it half as long again.
+
+use super::Note;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// Not `name:defaults:notes`
+    MissingSection,
+    InvalidDefault(String),
+    InvalidNote(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingSection => write!(f, "expected name:defaults:notes"),
+            Error::InvalidDefault(s) => write!(f, "invalid default: {}", s),
+            Error::InvalidNote(s) => write!(f, "invalid note: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ringtone {
+    pub name: String,
+    pub notes: Vec<Note>,
+}
+
+struct Defaults {
+    duration: u32,
+    octave: u32,
+    bpm: u32,
+}
+
+pub fn parse(text: &str) -> Result<Ringtone, Error> {
+    let mut sections = text.trim().splitn(3, ':');
+    let (name, defaults, notes) = match (sections.next(), sections.next(), sections.next()) {
+        (Some(name), Some(defaults), Some(notes)) => (name, defaults, notes),
+        _ => return Err(Error::MissingSection),
+    };
+
+    let defaults = parse_defaults(defaults)?;
+    let notes = notes
+        .split(',')
+        .map(str::trim)
+        .filter(|note| !note.is_empty())
+        .map(|note| parse_note(note, &defaults))
+        .collect::<Result<_, _>>()?;
+
+    Ok(Ringtone {
+        name: name.trim().to_string(),
+        notes,
+    })
+}
+
+/// Frequency of `semitone` (0 is C) in `octave`, A4 being 440 Hz
+pub fn frequency(semitone: u32, octave: u32) -> f64 {
+    let midi = 12 * (octave + 1) + semitone;
+    440.0 * 2_f64.powf((f64::from(midi) - 69.0) / 12.0)
+}
+
+fn parse_defaults(text: &str) -> Result<Defaults, Error> {
+    // the spec's own defaults, for anything left out
+    let mut defaults = Defaults {
+        duration: 4,
+        octave: 6,
+        bpm: 63,
+    };
+
+    for default in text.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+        let invalid = || Error::InvalidDefault(default.to_string());
+        let (key, value) = default.split_once('=').ok_or_else(invalid)?;
+        let value: u32 = value.trim().parse().map_err(|_| invalid())?;
+
+        match key.trim() {
+            "d" if is_duration(value) => defaults.duration = value,
+            "o" if value <= 8 => defaults.octave = value,
+            "b" if value > 0 => defaults.bpm = value,
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(defaults)
+}
+
+fn parse_note(text: &str, defaults: &Defaults) -> Result<Note, Error> {
+    let invalid = || Error::InvalidNote(text.to_string());
+    let text_lower = text.to_ascii_lowercase();
+    let mut rest = text_lower.as_str();
+
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let duration = match &rest[..digits] {
+        "" => defaults.duration,
+        d => d
+            .parse()
+            .ok()
+            .filter(|d| is_duration(*d))
+            .ok_or_else(invalid)?,
+    };
+    rest = &rest[digits..];
+
+    let mut chars = rest.chars();
+    let semitone = match chars.next() {
+        Some('c') => Some(0),
+        Some('d') => Some(2),
+        Some('e') => Some(4),
+        Some('f') => Some(5),
+        Some('g') => Some(7),
+        Some('a') => Some(9),
+        Some('b') | Some('h') => Some(11),
+        Some('p') => None,
+        _ => return Err(invalid()),
+    };
+    rest = chars.as_str();
+
+    let sharp = rest.starts_with('#');
+    if sharp {
+        rest = &rest[1..];
+    }
+
+    // the dot is found before and after the octave in the wild
+    let mut dotted = rest.starts_with('.');
+    if dotted {
+        rest = &rest[1..];
+    }
+
+    let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let octave = match &rest[..digits] {
+        "" => defaults.octave,
+        o => o.parse().ok().filter(|o| *o <= 8).ok_or_else(invalid)?,
+    };
+    rest = &rest[digits..];
+
+    if rest == "." && !dotted {
+        dotted = true;
+    } else if !rest.is_empty() {
+        return Err(invalid());
+    }
+
+    // a whole note is 4 beats
+    let mut ms = 240_000.0 / (f64::from(defaults.bpm) * f64::from(duration));
+    if dotted {
+        ms *= 1.5;
+    }
+    let duration = Duration::from_secs_f64(ms / 1000.0);
+
+    Ok(match semitone {
+        Some(semitone) => {
+            let semitone = semitone + u32::from(sharp);
+            Note::tone(frequency(semitone % 12, octave + semitone / 12), duration)
+        }
+        None => Note::rest(duration),
+    })
+}
+
+fn is_duration(value: u32) -> bool {
+    matches!(value, 1 | 2 | 4 | 8 | 16 | 32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_note(note: &Note, frequency: Option<f64>, ms: f64) {
+        match (note.frequency, frequency) {
+            (Some(actual), Some(expected)) => {
+                assert!((actual - expected).abs() < 0.01, "{} Hz", actual)
+            }
+            (actual, expected) => assert_eq!(actual, expected),
+        }
+        let actual = note.duration.as_secs_f64() * 1000.0;
+        assert!((actual - ms).abs() < 0.001, "{} ms", actual);
+    }
+
+    fn note(notes: &str) -> Note {
+        let ringtone = parse(&format!("test:d=4,o=5,b=120:{}", notes)).unwrap();
+        assert_eq!(ringtone.notes.len(), 1);
+        ringtone.notes[0]
+    }
+
+    #[test]
+    fn nokia_tune() {
+        let tune = parse("Nokia:d=4,o=5,b=180:8e6,8d6,f#,g#,8c#6,8b,d,e,8b,8a,c#,e,2a").unwrap();
+
+        assert_eq!(tune.name, "Nokia");
+        assert_eq!(tune.notes.len(), 13);
+        // a quarter is a beat, 333 ms at 180 bpm
+        assert_note(&tune.notes[0], Some(1318.51), 1000.0 / 6.0);
+        assert_note(&tune.notes[2], Some(739.99), 1000.0 / 3.0);
+        assert_note(&tune.notes[12], Some(880.0), 2000.0 / 3.0);
+    }
+
+    #[test]
+    fn spec_defaults() {
+        let tune = parse("x::c,p").unwrap();
+
+        // d=4, o=6, b=63
+        assert_note(&tune.notes[0], Some(1046.50), 240_000.0 / 63.0 / 4.0);
+        assert_note(&tune.notes[1], None, 240_000.0 / 63.0 / 4.0);
+    }
+
+    #[test]
+    fn dotted_notes() {
+        assert_note(&note("8a."), Some(880.0), 375.0);
+        assert_note(&note("8a.4"), Some(440.0), 375.0);
+        assert_note(&note("8a4."), Some(440.0), 375.0);
+        assert_note(&note("p."), None, 750.0);
+    }
+
+    #[test]
+    fn sharps_and_h() {
+        assert_note(&note("c#"), Some(554.37), 500.0);
+        assert_note(&note("h"), Some(987.77), 500.0);
+        assert_eq!(note("h"), note("b"));
+        // past B is the next octave's C
+        assert_eq!(note("b#"), note("c6"));
+    }
+
+    #[test]
+    fn whitespace_and_case() {
+        let tune = parse(" Tune : d=8, o=4, b=100 : C , 4P, ").unwrap();
+
+        assert_eq!(tune.name, "Tune");
+        assert_eq!(tune.notes.len(), 2);
+        assert_note(&tune.notes[0], Some(261.63), 300.0);
+        assert_note(&tune.notes[1], None, 600.0);
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert_eq!(parse("no sections"), Err(Error::MissingSection));
+        assert_eq!(parse("x:d=4"), Err(Error::MissingSection));
+        assert_eq!(
+            parse("x:d=3:c"),
+            Err(Error::InvalidDefault("d=3".to_string()))
+        );
+        assert_eq!(
+            parse("x:o=9:c"),
+            Err(Error::InvalidDefault("o=9".to_string()))
+        );
+        assert_eq!(
+            parse("x:b=0:c"),
+            Err(Error::InvalidDefault("b=0".to_string()))
+        );
+        assert_eq!(
+            parse("x:tempo:c"),
+            Err(Error::InvalidDefault("tempo".to_string()))
+        );
+
+        for bad in ["q", "64c", "c9", "c#x", "8", "c..", "4c.5."] {
+            assert_eq!(
+                parse(&format!("x::{}", bad)),
+                Err(Error::InvalidNote(bad.to_string())),
+                "{}",
+                bad
+            );
+        }
+    }
+}
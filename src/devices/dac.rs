@@ -0,0 +1,49 @@
+//! Digital to analog converters behind one [`Dac`] trait
+//!
+//! Like the ADC side, a converter works in codes from 0 to a full scale
+//! code, which maps linearly onto 0 V to its reference voltage.
+
+pub mod mcp4725;
+pub mod pcf8591;
+
+pub use mcp4725::MCP4725;
+pub use pcf8591::PCF8591;
+
+/// Voltage of `code`, for either direction of conversion
+pub fn code_to_voltage(code: u16, max_code: u16, reference: f64) -> f64 {
+    f64::from(code) / f64::from(max_code) * reference
+}
+
+/// The code closest to `volts`, clamped to the converter's range
+pub fn voltage_to_code(volts: f64, max_code: u16, reference: f64) -> u16 {
+    (volts / reference * f64::from(max_code))
+        .round()
+        .clamp(0.0, f64::from(max_code)) as u16
+}
+
+pub trait Dac {
+    type Error;
+
+    /// Code giving the reference voltage, 255 for 8 bits
+    fn max_code(&self) -> u16;
+
+    /// Output voltage at [`max_code`](Self::max_code)
+    fn reference(&self) -> f64;
+
+    fn set_code(&mut self, code: u16) -> Result<(), Self::Error>;
+
+    /// Sets `ratio` (0.0..=1.0) of the reference
+    fn set_ratio(&mut self, ratio: f64) -> Result<(), Self::Error> {
+        let max = f64::from(self.max_code());
+        self.set_code((ratio.clamp(0.0, 1.0) * max).round() as u16)
+    }
+
+    /// Sets the output closest to `volts`, returns the voltage it set
+    fn set_voltage(&mut self, volts: f64) -> Result<f64, Self::Error> {
+        let (max, reference) = (self.max_code(), self.reference());
+        let code = voltage_to_code(volts, max, reference);
+
+        self.set_code(code)?;
+        Ok(code_to_voltage(code, max, reference))
+    }
+}
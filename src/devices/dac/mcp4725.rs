@@ -0,0 +1,215 @@
+//! MCP4725, 12-bit DAC with EEPROM
+//!
+//! The reference is VDD, so a 5V supply gives 0 to 5 V out. The EEPROM
+//! holds the code and power-down mode loaded at power-on.
+
+use super::Dac;
+use embedded_hal::blocking::i2c::{Read, Write};
+use std::fmt;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// A0 low, many breakouts ship with 0x62 instead
+pub const DEFAULT_ADDR: u8 = 0x60;
+pub const MAX_CODE: u16 = 0x0fff;
+
+const WRITE_DAC: u8 = 0x40;
+const WRITE_DAC_EEPROM: u8 = 0x60;
+const STATUS_READY: u8 = 1 << 7;
+const STATUS_POR: u8 = 1 << 6;
+/// The datasheet's worst case EEPROM write time
+const EEPROM_WRITE: Duration = Duration::from_millis(50);
+
+#[derive(Debug)]
+pub enum Error<E> {
+    I2c(E),
+    /// The EEPROM write didn't finish in time, the power-on output may be
+    /// the old one
+    EepromBusy,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::I2c(e)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "i2c error: {:?}", e),
+            Error::EepromBusy => write!(f, "eeprom write timed out"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+/// What the output does while powered down
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PowerDown {
+    /// Not powered down
+    Normal = 0,
+    /// Pulled to ground through 1k
+    Pull1k = 1,
+    Pull100k = 2,
+    Pull500k = 3,
+}
+
+impl PowerDown {
+    fn from_bits(bits: u8) -> Self {
+        match bits & 0b11 {
+            0 => PowerDown::Normal,
+            1 => PowerDown::Pull1k,
+            2 => PowerDown::Pull100k,
+            _ => PowerDown::Pull500k,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Status {
+    /// Not busy writing the EEPROM
+    pub ready: bool,
+    /// Powered on and out of reset
+    pub power_on: bool,
+    pub code: u16,
+    pub power_down: PowerDown,
+    /// Loaded at power-on
+    pub eeprom_code: u16,
+    pub eeprom_power_down: PowerDown,
+}
+
+pub struct MCP4725<I2C> {
+    i2c: I2C,
+    addr: u8,
+    /// VDD
+    reference: f64,
+    code: u16,
+}
+
+impl<I2C, E> MCP4725<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    /// Creates a new `MCP4725`, the output keeps its current code, which
+    /// is read back
+    pub fn new(i2c: I2C, addr: u8, reference: f64) -> Result<Self, E> {
+        let mut dac = Self {
+            i2c,
+            addr,
+            reference,
+            code: 0,
+        };
+        dac.code = dac.status()?.code;
+
+        Ok(dac)
+    }
+
+    /// Sets the output in a 2 byte transfer, also powers it up
+    pub fn write_fast(&mut self, code: u16) -> Result<(), E> {
+        self.fast(code, PowerDown::Normal)
+    }
+
+    /// Sets the output through the DAC register, as a normal write
+    pub fn write(&mut self, code: u16) -> Result<(), E> {
+        self.write_register(WRITE_DAC, code, PowerDown::Normal)
+    }
+
+    /// Sets the output and stores it in the EEPROM, so it's the power-on
+    /// output too
+    ///
+    /// Blocks until the EEPROM is written, up to 50 ms. The EEPROM wears
+    /// out after about a million writes, don't call this in a loop.
+    pub fn persist(&mut self, code: u16) -> Result<(), Error<E>> {
+        self.write_register(WRITE_DAC_EEPROM, code, PowerDown::Normal)?;
+
+        let deadline = Instant::now() + EEPROM_WRITE;
+        loop {
+            // RDY only goes low once the write has started, reading it
+            // straight away can still see it high
+            sleep(Duration::from_millis(5));
+            if self.status()?.ready {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::EepromBusy);
+            }
+        }
+    }
+
+    /// Turns the output off, [`Normal`](PowerDown::Normal) turns it back on
+    pub fn power_down(&mut self, mode: PowerDown) -> Result<(), E> {
+        self.fast(self.code, mode)
+    }
+
+    /// Powers the output back up at its last code
+    pub fn wake(&mut self) -> Result<(), E> {
+        self.fast(self.code, PowerDown::Normal)
+    }
+
+    pub fn code(&self) -> u16 {
+        self.code
+    }
+
+    pub fn status(&mut self) -> Result<Status, E> {
+        let mut buf = [0; 5];
+        self.i2c.read(self.addr, &mut buf)?;
+
+        Ok(Status {
+            ready: buf[0] & STATUS_READY != 0,
+            power_on: buf[0] & STATUS_POR != 0,
+            code: u16::from(buf[1]) << 4 | u16::from(buf[2]) >> 4,
+            power_down: PowerDown::from_bits(buf[0] >> 1),
+            eeprom_code: u16::from(buf[3] & 0x0f) << 8 | u16::from(buf[4]),
+            eeprom_power_down: PowerDown::from_bits(buf[3] >> 5),
+        })
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn fast(&mut self, code: u16, mode: PowerDown) -> Result<(), E> {
+        let code = code.min(MAX_CODE);
+        let [hi, lo] = code.to_be_bytes();
+
+        self.i2c.write(self.addr, &[(mode as u8) << 4 | hi, lo])?;
+        self.code = code;
+        Ok(())
+    }
+
+    fn write_register(&mut self, command: u8, code: u16, mode: PowerDown) -> Result<(), E> {
+        let code = code.min(MAX_CODE);
+        let bytes = [
+            command | (mode as u8) << 1,
+            (code >> 4) as u8,
+            (code << 4) as u8,
+        ];
+
+        self.i2c.write(self.addr, &bytes)?;
+        self.code = code;
+        Ok(())
+    }
+}
+
+impl<I2C, E> Dac for MCP4725<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    type Error = E;
+
+    fn max_code(&self) -> u16 {
+        MAX_CODE
+    }
+
+    fn reference(&self) -> f64 {
+        self.reference
+    }
+
+    fn set_code(&mut self, code: u16) -> Result<(), E> {
+        self.write_fast(code)
+    }
+}
@@ -0,0 +1,96 @@
+//! PCF8591, 8-bit DAC and 4-channel 8-bit ADC
+//!
+//! The output and the inputs share one control byte, so reading an input
+//! keeps the output enabled.
+
+use super::Dac;
+use embedded_hal::blocking::i2c::{Read, Write};
+
+/// A0..A2 low
+pub const DEFAULT_ADDR: u8 = 0x48;
+pub const CHANNELS: u8 = 4;
+
+const ANALOG_OUTPUT_ENABLE: u8 = 1 << 6;
+
+pub struct PCF8591<I2C> {
+    i2c: I2C,
+    addr: u8,
+    /// VREF, usually VCC on the modules
+    reference: f64,
+    output_enabled: bool,
+    code: u8,
+}
+
+impl<I2C, E> PCF8591<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    /// Creates a new `PCF8591`, its output isn't touched until written
+    pub fn new(i2c: I2C, addr: u8, reference: f64) -> Self {
+        Self {
+            i2c,
+            addr,
+            reference,
+            output_enabled: false,
+            code: 0,
+        }
+    }
+
+    /// Enables the output at `code`
+    pub fn write(&mut self, code: u8) -> Result<(), E> {
+        self.i2c.write(self.addr, &[ANALOG_OUTPUT_ENABLE, code])?;
+        self.output_enabled = true;
+        self.code = code;
+        Ok(())
+    }
+
+    /// Turns the output off, it's then high impedance
+    pub fn disable_output(&mut self) -> Result<(), E> {
+        self.i2c.write(self.addr, &[0])?;
+        self.output_enabled = false;
+        Ok(())
+    }
+
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    /// Converts single ended input `channel` (0..=3)
+    pub fn read_input(&mut self, channel: u8) -> Result<u8, E> {
+        assert!(channel < CHANNELS, "channel out of range");
+
+        let mut control = channel;
+        if self.output_enabled {
+            control |= ANALOG_OUTPUT_ENABLE;
+        }
+        self.i2c.write(self.addr, &[control])?;
+
+        // the first byte is the conversion from before this request
+        let mut buf = [0; 2];
+        self.i2c.read(self.addr, &mut buf)?;
+        Ok(buf[1])
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> Dac for PCF8591<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    type Error = E;
+
+    fn max_code(&self) -> u16 {
+        255
+    }
+
+    fn reference(&self) -> f64 {
+        self.reference
+    }
+
+    fn set_code(&mut self, code: u16) -> Result<(), E> {
+        self.write(code.min(255) as u8)
+    }
+}
@@ -0,0 +1,235 @@
+//! DHT11 and DHT22 (AM2302) temperature and humidity sensors
+//!
+//! The single data line is bit-banged: the host pulls it low to start, the
+//! sensor answers with 40 bits, each a 50 us low followed by a 26-28 us (0)
+//! or 70 us (1) high. Linux can preempt us in the middle of a frame, so
+//! expect the odd [`Error::Timeout`] and use [`Dht::read_retry`].
+//!
+//! **NOTE** The line needs a pull-up, most modules have one already
+
+use crate::utils::units::{Humidity, Temperature};
+use rppal::gpio::{IoPin, Level, Mode};
+use std::fmt;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+const BITS: usize = 40;
+/// Longest pulse of a healthy frame is 80 us
+const PULSE_TIMEOUT: Duration = Duration::from_micros(200);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Model {
+    /// 0 to 50 °C and 20 to 90 % in whole units
+    Dht11,
+    /// -40 to 80 °C and 0 to 100 % in tenths
+    Dht22,
+}
+
+impl Model {
+    /// Shortest time between two reads
+    pub fn min_interval(self) -> Duration {
+        match self {
+            Model::Dht11 => Duration::from_secs(1),
+            Model::Dht22 => Duration::from_secs(2),
+        }
+    }
+
+    /// How long the host holds the line low to start a read
+    fn start_pulse(self) -> Duration {
+        match self {
+            Model::Dht11 => Duration::from_millis(18),
+            Model::Dht22 => Duration::from_millis(2),
+        }
+    }
+
+    fn decode(self, data: [u8; 5]) -> Reading {
+        let (humidity, temperature) = match self {
+            Model::Dht11 => {
+                let humidity = f64::from(data[0]) + f64::from(data[1]) / 10.0;
+                let temperature = f64::from(data[2]) + f64::from(data[3] & 0x7f) / 10.0;
+                let sign = if data[3] & 0x80 != 0 { -1.0 } else { 1.0 };
+                (humidity, sign * temperature)
+            }
+            Model::Dht22 => {
+                let humidity = f64::from(u16::from_be_bytes([data[0], data[1]])) / 10.0;
+                let temperature = f64::from(u16::from_be_bytes([data[2] & 0x7f, data[3]])) / 10.0;
+                let sign = if data[2] & 0x80 != 0 { -1.0 } else { 1.0 };
+                (humidity, sign * temperature)
+            }
+        };
+
+        Reading {
+            temperature: Temperature::from_celsius(temperature),
+            humidity: Humidity::from_percent(humidity),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Reading {
+    pub temperature: Temperature,
+    pub humidity: Humidity,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The sensor didn't answer the start signal, check wiring and power
+    NoResponse,
+    /// A pulse took too long, the frame was lost part way
+    Timeout,
+    /// The frame arrived but its checksum doesn't match
+    Checksum { received: u8, computed: u8 },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoResponse => write!(f, "no response from the sensor"),
+            Error::Timeout => write!(f, "timeout while reading the frame"),
+            Error::Checksum { received, computed } => write!(
+                f,
+                "checksum mismatch: received {:#04x}, computed {:#04x}",
+                received, computed
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub struct Dht {
+    pin: IoPin,
+    model: Model,
+    last_read: Option<Instant>,
+}
+
+impl Dht {
+    /// `pin` is switched between input and output as needed
+    pub fn new(mut pin: IoPin, model: Model) -> Self {
+        pin.set_mode(Mode::Input);
+
+        Self {
+            pin,
+            model,
+            last_read: None,
+        }
+    }
+
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Reads temperature and humidity, waiting first if the previous read
+    /// was less than [`Model::min_interval`] ago
+    pub fn read(&mut self) -> Result<Reading, Error> {
+        if let Some(last_read) = self.last_read {
+            let next = last_read + self.model.min_interval();
+            sleep(next.saturating_duration_since(Instant::now()));
+        }
+
+        let data = self.read_frame();
+        self.last_read = Some(Instant::now());
+        let data = data?;
+        checksum(&data)?;
+
+        Ok(self.model.decode(data))
+    }
+
+    /// Reads up to `attempts` times, returns the last error if none worked
+    pub fn read_retry(&mut self, attempts: usize) -> Result<Reading, Error> {
+        let mut result = self.read();
+        for _ in 1..attempts {
+            if result.is_ok() {
+                break;
+            }
+            result = self.read();
+        }
+
+        result
+    }
+
+    pub fn release(self) -> IoPin {
+        self.pin
+    }
+
+    fn read_frame(&mut self) -> Result<[u8; 5], Error> {
+        self.pin.set_mode(Mode::Output);
+        self.pin.set_low();
+        sleep(self.model.start_pulse());
+        self.pin.set_high();
+        self.pin.set_mode(Mode::Input);
+
+        // the sensor pulls low 20-40 us later, then 80 us low, 80 us high
+        self.wait_for(Level::Low).ok_or(Error::NoResponse)?;
+        self.wait_for(Level::High).ok_or(Error::NoResponse)?;
+        self.wait_for(Level::Low).ok_or(Error::NoResponse)?;
+
+        let mut data = [0; 5];
+        for bit in 0..BITS {
+            let low = self.wait_for(Level::High).ok_or(Error::Timeout)?;
+            let high = self.wait_for(Level::Low).ok_or(Error::Timeout)?;
+
+            // comparing against the 50 us low copes with slow polling better
+            // than a fixed threshold
+            if high > low {
+                data[bit / 8] |= 0x80 >> (bit % 8);
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Busy waits until the line is at `level`, returns how long it took
+    fn wait_for(&self, level: Level) -> Option<Duration> {
+        let start = Instant::now();
+        while self.pin.read() != level {
+            if start.elapsed() > PULSE_TIMEOUT {
+                return None;
+            }
+        }
+
+        Some(start.elapsed())
+    }
+}
+
+/// The last byte is the sum of the others
+fn checksum(data: &[u8; 5]) -> Result<(), Error> {
+    let computed = data[..4].iter().fold(0_u8, |sum, b| sum.wrapping_add(*b));
+    if computed != data[4] {
+        return Err(Error::Checksum {
+            received: data[4],
+            computed,
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_negative_dht22() {
+        // 65.2 % and -10.1 °C
+        let data = [0x02, 0x8c, 0x80, 0x65, 0x73];
+        assert_eq!(checksum(&data), Ok(()));
+
+        let reading = Model::Dht22.decode(data);
+        assert!((reading.temperature.celsius() + 10.1).abs() < 1e-9);
+        assert!((reading.humidity.percent() - 65.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rejects_checksum_mismatch() {
+        assert_eq!(
+            checksum(&[0x02, 0x8c, 0x80, 0x65, 0x74]),
+            Err(Error::Checksum {
+                received: 0x74,
+                computed: 0x73
+            })
+        );
+    }
+}
@@ -0,0 +1,232 @@
+//! DS18B20 1-Wire thermometers through the kernel's w1 sysfs interface
+//!
+//! Enable the bus with `dtoverlay=w1-gpio` (GPIO 4 by default) in
+//! `/boot/config.txt`. Every probe on the bus shows up as a directory named
+//! after its ROM ID, such as `28-0316a279f2ff`.
+
+use crate::utils::units::Temperature;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+pub const W1_DEVICES: &str = "/sys/bus/w1/devices";
+
+/// Families read by the kernel's w1_therm driver
+const FAMILIES: [u8; 3] = [
+    0x10, // DS18S20
+    0x22, // DS1822
+    0x28, // DS18B20
+];
+
+/// The scratchpad's value after power-on, before any conversion
+const POWER_ON_RESET: i32 = 85_000;
+
+/// Family code and 48-bit serial number of a 1-Wire device
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RomId {
+    pub family: u8,
+    pub serial: u64,
+}
+
+impl FromStr for RomId {
+    type Err = Error;
+
+    /// Parses the sysfs name, `ff-ssssssssssss` in hex
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidRomId(s.to_string());
+        let (family, serial) = s.split_once('-').ok_or_else(invalid)?;
+        if family.len() != 2 || serial.len() != 12 {
+            return Err(invalid());
+        }
+
+        Ok(Self {
+            family: u8::from_str_radix(family, 16).map_err(|_| invalid())?,
+            serial: u64::from_str_radix(serial, 16).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl fmt::Display for RomId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02x}-{:012x}", self.family, self.serial)
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The probe isn't on the bus (anymore)
+    NotFound(RomId),
+    InvalidRomId(String),
+    /// The reading was corrupted on the wire
+    Crc,
+    /// The probe lost power, or is powered parasitically without a strong
+    /// pull-up, and reports its power-on value
+    PowerOnReset,
+    /// The driver's output isn't in the expected format
+    Parse(String),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "io error: {}", e),
+            Error::NotFound(rom) => write!(f, "probe {} not found", rom),
+            Error::InvalidRomId(s) => write!(f, "invalid ROM ID: {}", s),
+            Error::Crc => write!(f, "crc error"),
+            Error::PowerOnReset => write!(f, "probe reports its power-on value"),
+            Error::Parse(s) => write!(f, "unexpected w1_slave output: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The temperature probes in `dir`, sorted by ROM ID
+pub fn discover_in<P: AsRef<Path>>(dir: P) -> Result<Vec<DS18B20>, Error> {
+    let mut probes = Vec::new();
+
+    for entry in fs::read_dir(dir.as_ref())? {
+        let entry = entry?;
+        // the bus master and other families are skipped
+        let rom = match entry.file_name().to_str().map(RomId::from_str) {
+            Some(Ok(rom)) if FAMILIES.contains(&rom.family) => rom,
+            _ => continue,
+        };
+        probes.push(DS18B20::with_dir(dir.as_ref(), rom));
+    }
+
+    probes.sort_by_key(|probe| probe.rom);
+    Ok(probes)
+}
+
+/// The temperature probes on the bus, sorted by ROM ID
+pub fn discover() -> Result<Vec<DS18B20>, Error> {
+    discover_in(W1_DEVICES)
+}
+
+pub struct DS18B20 {
+    rom: RomId,
+    path: PathBuf,
+}
+
+impl DS18B20 {
+    pub fn new(rom: RomId) -> Self {
+        Self::with_dir(W1_DEVICES, rom)
+    }
+
+    /// A probe under another sysfs directory
+    pub fn with_dir<P: AsRef<Path>>(dir: P, rom: RomId) -> Self {
+        Self {
+            rom,
+            path: dir.as_ref().join(rom.to_string()).join("w1_slave"),
+        }
+    }
+
+    pub fn rom(&self) -> RomId {
+        self.rom
+    }
+
+    /// Starts a conversion and waits for it, up to 750 ms at 12 bits
+    pub fn read(&self) -> Result<Temperature, Error> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(Error::NotFound(self.rom)),
+            Err(e) => return Err(e.into()),
+        };
+
+        parse(&text)
+    }
+}
+
+/// Parses w1_slave, for example:
+///
+/// ```text
+/// 72 01 4b 46 7f ff 0e 10 57 : crc=57 YES
+/// 72 01 4b 46 7f ff 0e 10 57 t=23125
+/// ```
+fn parse(text: &str) -> Result<Temperature, Error> {
+    let mut lines = text.lines();
+    let parse_error = || Error::Parse(text.trim().to_string());
+
+    let crc = lines.next().ok_or_else(parse_error)?;
+    if !crc.trim_end().ends_with("YES") {
+        return Err(Error::Crc);
+    }
+
+    let millicelsius: i32 = lines
+        .next()
+        .and_then(|line| line.rsplit_once("t="))
+        .and_then(|(_, t)| t.trim().parse().ok())
+        .ok_or_else(parse_error)?;
+
+    if millicelsius == POWER_ON_RESET {
+        return Err(Error::PowerOnReset);
+    }
+
+    Ok(Temperature::from_celsius(f64::from(millicelsius) / 1000.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_w1_slave() {
+        let text = "72 01 4b 46 7f ff 0e 10 57 : crc=57 YES\n\
+                    72 01 4b 46 7f ff 0e 10 57 t=23125\n";
+        assert_eq!(parse(text).unwrap().celsius(), 23.125);
+
+        let negative = "5e ff 4b 46 7f ff 02 10 dd : crc=dd YES\n\
+                        5e ff 4b 46 7f ff 02 10 dd t=-10125\n";
+        assert_eq!(parse(negative).unwrap().celsius(), -10.125);
+    }
+
+    #[test]
+    fn rejects_bad_readings() {
+        let crc = "72 01 4b 46 7f ff 0e 10 57 : crc=12 NO\n\
+                   72 01 4b 46 7f ff 0e 10 57 t=23125\n";
+        assert!(matches!(parse(crc), Err(Error::Crc)));
+
+        let reset = "50 05 4b 46 7f ff 0c 10 1c : crc=1c YES\n\
+                     50 05 4b 46 7f ff 0c 10 1c t=85000\n";
+        assert!(matches!(parse(reset), Err(Error::PowerOnReset)));
+
+        for malformed in ["", "crc=57 YES", "crc=57 YES\nt=", "crc=57 YES\nt=abc"] {
+            assert!(matches!(parse(malformed), Err(Error::Parse(_))));
+        }
+    }
+
+    #[test]
+    fn rom_id_round_trip() {
+        let rom: RomId = "28-0316a279f2ff".parse().unwrap();
+        assert_eq!(
+            rom,
+            RomId {
+                family: 0x28,
+                serial: 0x0316_a279_f2ff
+            }
+        );
+        assert_eq!(rom.to_string(), "28-0316a279f2ff");
+
+        for invalid in [
+            "w1_bus_master1",
+            "28-0316a279f2",
+            "zz-0316a279f2ff",
+            "28_0316a279f2ff",
+        ] {
+            assert!(matches!(
+                invalid.parse::<RomId>(),
+                Err(Error::InvalidRomId(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,130 @@
+//! I2C GPIO expanders, for when the Pi runs out of pins
+//!
+//! Share an expander with [`into_shared`](mcp23017::MCP23017::into_shared),
+//! then every pin can be an [`Output`] or [`Input`] implementing the
+//! embedded-hal traits, so a `Motor`'s direction pins or a stepper's coils
+//! can live on the expander.
+
+use crate::utils::lock;
+use embedded_hal::digital::v2::{InputPin, OutputPin, StatefulOutputPin};
+use std::sync::{Arc, Mutex};
+
+pub mod mcp23017;
+pub mod pcf8574;
+
+pub use mcp23017::MCP23017;
+pub use pcf8574::PCF8574;
+
+/// Pin level access common to the expanders
+///
+/// Pin numbers past [`pins`](Self::pins) panic.
+pub trait Expander {
+    type Error;
+
+    /// Number of pins
+    fn pins(&self) -> u8;
+
+    fn set_output(&mut self, pin: u8) -> Result<(), Self::Error>;
+
+    fn set_input(&mut self, pin: u8) -> Result<(), Self::Error>;
+
+    fn set_pin(&mut self, pin: u8, high: bool) -> Result<(), Self::Error>;
+
+    /// The level last written to `pin`
+    fn is_set(&self, pin: u8) -> bool;
+
+    fn read_pin(&mut self, pin: u8) -> Result<bool, Self::Error>;
+}
+
+/// One expander pin as an embedded-hal `OutputPin`
+pub struct Output<X> {
+    expander: Arc<Mutex<X>>,
+    pin: u8,
+}
+
+impl<X, E> Output<X>
+where
+    X: Expander<Error = E>,
+{
+    /// Makes `pin` an output, low
+    pub fn new(expander: &Arc<Mutex<X>>, pin: u8) -> Result<Self, E> {
+        let mut x = lock(expander);
+        assert!(pin < x.pins(), "pin out of range");
+
+        x.set_pin(pin, false)?;
+        x.set_output(pin)?;
+
+        Ok(Self {
+            expander: expander.clone(),
+            pin,
+        })
+    }
+}
+
+impl<X, E> OutputPin for Output<X>
+where
+    X: Expander<Error = E>,
+{
+    type Error = E;
+
+    fn set_low(&mut self) -> Result<(), E> {
+        lock(&self.expander).set_pin(self.pin, false)
+    }
+
+    fn set_high(&mut self) -> Result<(), E> {
+        lock(&self.expander).set_pin(self.pin, true)
+    }
+}
+
+impl<X, E> StatefulOutputPin for Output<X>
+where
+    X: Expander<Error = E>,
+{
+    fn is_set_high(&self) -> Result<bool, E> {
+        Ok(lock(&self.expander).is_set(self.pin))
+    }
+
+    fn is_set_low(&self) -> Result<bool, E> {
+        Ok(!lock(&self.expander).is_set(self.pin))
+    }
+}
+
+/// One expander pin as an embedded-hal `InputPin`
+///
+/// Every read is an I2C transfer.
+pub struct Input<X> {
+    expander: Arc<Mutex<X>>,
+    pin: u8,
+}
+
+impl<X, E> Input<X>
+where
+    X: Expander<Error = E>,
+{
+    pub fn new(expander: &Arc<Mutex<X>>, pin: u8) -> Result<Self, E> {
+        let mut x = lock(expander);
+        assert!(pin < x.pins(), "pin out of range");
+
+        x.set_input(pin)?;
+
+        Ok(Self {
+            expander: expander.clone(),
+            pin,
+        })
+    }
+}
+
+impl<X, E> InputPin for Input<X>
+where
+    X: Expander<Error = E>,
+{
+    type Error = E;
+
+    fn is_high(&self) -> Result<bool, E> {
+        lock(&self.expander).read_pin(self.pin)
+    }
+
+    fn is_low(&self) -> Result<bool, E> {
+        Ok(!self.is_high()?)
+    }
+}
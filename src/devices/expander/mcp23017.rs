@@ -0,0 +1,239 @@
+//! MCP23017, 16-bit I/O expander with pull-ups and interrupts
+//!
+//! Pins 0..7 are GPA0..GPA7 and 8..15 GPB0..GPB7. Registers are used in
+//! the default bank 0 layout, where each A register is followed by its B
+//! twin so both ports are written in one transfer.
+
+use super::Expander;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use std::sync::{Arc, Mutex};
+
+/// A0..A2 low
+pub const DEFAULT_ADDR: u8 = 0x20;
+
+const IODIR: u8 = 0x00;
+const IPOL: u8 = 0x02;
+const GPINTEN: u8 = 0x04;
+const DEFVAL: u8 = 0x06;
+const INTCON: u8 = 0x08;
+const IOCON: u8 = 0x0a;
+const GPPU: u8 = 0x0c;
+const INTF: u8 = 0x0e;
+const INTCAP: u8 = 0x10;
+const GPIO: u8 = 0x12;
+const OLAT: u8 = 0x14;
+
+const IOCON_MIRROR: u8 = 1 << 6;
+const IOCON_ODR: u8 = 1 << 2;
+const IOCON_INTPOL: u8 = 1 << 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interrupt {
+    Disabled,
+    /// On every change of the pin
+    Change,
+    /// While the pin differs from the given level
+    Compare(bool),
+}
+
+/// How INTA and INTB signal
+#[derive(Copy, Clone, Debug)]
+pub struct IntConfig {
+    /// Both outputs fire for either port, so one GPIO is enough
+    pub mirror: bool,
+    /// Open drain, several chips can share a line with a pull-up
+    pub open_drain: bool,
+    /// Ignored for open drain, which is always active low
+    pub active_high: bool,
+}
+
+impl Default for IntConfig {
+    fn default() -> Self {
+        Self {
+            mirror: true,
+            open_drain: false,
+            active_high: false,
+        }
+    }
+}
+
+pub struct MCP23017<I2C> {
+    i2c: I2C,
+    addr: u8,
+    iodir: u16,
+    olat: u16,
+    gppu: u16,
+    ipol: u16,
+    gpinten: u16,
+    defval: u16,
+    intcon: u16,
+}
+
+impl<I2C, E> MCP23017<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Creates a new `MCP23017`, every pin an input without pull-up, the
+    /// power-on state
+    pub fn new(i2c: I2C, addr: u8) -> Result<Self, E> {
+        let mut mcp = Self {
+            i2c,
+            addr,
+            iodir: 0xffff,
+            olat: 0,
+            gppu: 0,
+            ipol: 0,
+            gpinten: 0,
+            defval: 0,
+            intcon: 0,
+        };
+
+        mcp.set_int_config(IntConfig::default())?;
+        mcp.write_reg16(IODIR, mcp.iodir)?;
+        mcp.write_reg16(OLAT, mcp.olat)?;
+        mcp.write_reg16(GPPU, mcp.gppu)?;
+        mcp.write_reg16(IPOL, mcp.ipol)?;
+        mcp.write_reg16(GPINTEN, mcp.gpinten)?;
+
+        Ok(mcp)
+    }
+
+    /// Enables the 100k pull-up of an input
+    ///
+    /// # Panics
+    ///
+    /// If `pin` is not below 16, like the other per-pin settings.
+    pub fn set_pull_up(&mut self, pin: u8, enabled: bool) -> Result<(), E> {
+        self.gppu = with_bit(self.gppu, pin, enabled);
+        self.write_reg16(GPPU, self.gppu)
+    }
+
+    /// Inverts what is read from an input, handy for buttons to ground
+    pub fn set_polarity(&mut self, pin: u8, inverted: bool) -> Result<(), E> {
+        self.ipol = with_bit(self.ipol, pin, inverted);
+        self.write_reg16(IPOL, self.ipol)
+    }
+
+    pub fn set_interrupt(&mut self, pin: u8, interrupt: Interrupt) -> Result<(), E> {
+        self.gpinten = with_bit(self.gpinten, pin, interrupt != Interrupt::Disabled);
+
+        if let Interrupt::Compare(level) = interrupt {
+            self.defval = with_bit(self.defval, pin, level);
+            self.write_reg16(DEFVAL, self.defval)?;
+        }
+        self.intcon = with_bit(self.intcon, pin, matches!(interrupt, Interrupt::Compare(_)));
+        self.write_reg16(INTCON, self.intcon)?;
+
+        self.write_reg16(GPINTEN, self.gpinten)
+    }
+
+    pub fn set_int_config(&mut self, config: IntConfig) -> Result<(), E> {
+        let mut iocon = 0;
+        if config.mirror {
+            iocon |= IOCON_MIRROR;
+        }
+        if config.open_drain {
+            iocon |= IOCON_ODR;
+        }
+        if config.active_high {
+            iocon |= IOCON_INTPOL;
+        }
+
+        // IOCON is one register mapped at both addresses
+        self.i2c.write(self.addr, &[IOCON, iocon])
+    }
+
+    /// The pins that caused the pending interrupt
+    pub fn interrupt_flags(&mut self) -> Result<u16, E> {
+        self.read_reg16(INTF)
+    }
+
+    /// The port as it was when the interrupt fired, reading it clears the
+    /// interrupt
+    pub fn interrupt_capture(&mut self) -> Result<u16, E> {
+        self.read_reg16(INTCAP)
+    }
+
+    /// Writes every output at once, bit 0 is GPA0
+    pub fn write_port(&mut self, port: u16) -> Result<(), E> {
+        self.write_reg16(OLAT, port)?;
+        self.olat = port;
+        Ok(())
+    }
+
+    /// Reads every pin at once, reading also clears the interrupt
+    pub fn read_port(&mut self) -> Result<u16, E> {
+        self.read_reg16(GPIO)
+    }
+
+    /// Shares the expander so each pin can be an
+    /// [`Output`](super::Output) or [`Input`](super::Input)
+    pub fn into_shared(self) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn write_reg16(&mut self, reg: u8, value: u16) -> Result<(), E> {
+        let [a, b] = value.to_le_bytes();
+        self.i2c.write(self.addr, &[reg, a, b])
+    }
+
+    fn read_reg16(&mut self, reg: u8) -> Result<u16, E> {
+        let mut buf = [0; 2];
+        self.i2c.write_read(self.addr, &[reg], &mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+}
+
+impl<I2C, E> Expander for MCP23017<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = E;
+
+    fn pins(&self) -> u8 {
+        16
+    }
+
+    fn set_output(&mut self, pin: u8) -> Result<(), E> {
+        self.iodir = with_bit(self.iodir, pin, false);
+        self.write_reg16(IODIR, self.iodir)
+    }
+
+    fn set_input(&mut self, pin: u8) -> Result<(), E> {
+        self.iodir = with_bit(self.iodir, pin, true);
+        self.write_reg16(IODIR, self.iodir)
+    }
+
+    fn set_pin(&mut self, pin: u8, high: bool) -> Result<(), E> {
+        let olat = with_bit(self.olat, pin, high);
+        if olat == self.olat {
+            return Ok(());
+        }
+        self.write_port(olat)
+    }
+
+    fn is_set(&self, pin: u8) -> bool {
+        self.olat & bit(pin) != 0
+    }
+
+    fn read_pin(&mut self, pin: u8) -> Result<bool, E> {
+        Ok(self.read_port()? & bit(pin) != 0)
+    }
+}
+
+fn with_bit(value: u16, pin: u8, set: bool) -> u16 {
+    if set {
+        value | bit(pin)
+    } else {
+        value & !bit(pin)
+    }
+}
+
+fn bit(pin: u8) -> u16 {
+    assert!(pin < 16, "pin out of range");
+    1 << pin
+}
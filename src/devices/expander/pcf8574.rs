@@ -0,0 +1,110 @@
+//! PCF8574, 8-bit quasi-bidirectional I/O expander
+//!
+//! There are no direction registers: a pin written low sinks current, a
+//! pin written high is pulled up weakly (about 100 uA) and can be read, or
+//! pulled low from outside. Outputs should therefore sink, e.g. LEDs to
+//! VCC. INT goes low on any input change until the port is read.
+
+use super::Expander;
+use embedded_hal::blocking::i2c::{Read, Write};
+use std::sync::{Arc, Mutex};
+
+/// A0..A2 low, the PCF8574A starts at 0x38
+pub const DEFAULT_ADDR: u8 = 0x20;
+
+pub struct PCF8574<I2C> {
+    i2c: I2C,
+    addr: u8,
+    /// Last byte written, 1 for inputs and high outputs
+    latch: u8,
+}
+
+impl<I2C, E> PCF8574<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    /// Creates a new `PCF8574` with every pin high, i.e. an input
+    pub fn new(i2c: I2C, addr: u8) -> Result<Self, E> {
+        let mut pcf = Self {
+            i2c,
+            addr,
+            latch: 0xff,
+        };
+        pcf.write_port(0xff)?;
+
+        Ok(pcf)
+    }
+
+    pub fn write_port(&mut self, byte: u8) -> Result<(), E> {
+        self.i2c.write(self.addr, &[byte])?;
+        self.latch = byte;
+        Ok(())
+    }
+
+    /// Pins written low read low whatever is connected
+    pub fn read_port(&mut self) -> Result<u8, E> {
+        let mut buf = [0];
+        self.i2c.read(self.addr, &mut buf)?;
+        Ok(buf[0])
+    }
+
+    pub fn latch(&self) -> u8 {
+        self.latch
+    }
+
+    /// Shares the expander so each pin can be an
+    /// [`Output`](super::Output) or [`Input`](super::Input)
+    pub fn into_shared(self) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+}
+
+impl<I2C, E> Expander for PCF8574<I2C>
+where
+    I2C: Write<Error = E> + Read<Error = E>,
+{
+    type Error = E;
+
+    fn pins(&self) -> u8 {
+        8
+    }
+
+    fn set_output(&mut self, _pin: u8) -> Result<(), E> {
+        Ok(())
+    }
+
+    /// Releases the pin high so it can be driven from outside
+    fn set_input(&mut self, pin: u8) -> Result<(), E> {
+        self.set_pin(pin, true)
+    }
+
+    fn set_pin(&mut self, pin: u8, high: bool) -> Result<(), E> {
+        let byte = if high {
+            self.latch | bit(pin)
+        } else {
+            self.latch & !bit(pin)
+        };
+
+        if byte == self.latch {
+            return Ok(());
+        }
+        self.write_port(byte)
+    }
+
+    fn is_set(&self, pin: u8) -> bool {
+        self.latch & bit(pin) != 0
+    }
+
+    fn read_pin(&mut self, pin: u8) -> Result<bool, E> {
+        Ok(self.read_port()? & bit(pin) != 0)
+    }
+}
+
+fn bit(pin: u8) -> u8 {
+    assert!(pin < 8, "pin out of range");
+    1 << pin
+}
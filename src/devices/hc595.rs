@@ -0,0 +1,169 @@
+//! 74HC595, 8-bit serial-in parallel-out shift register
+
+use crate::utils::lock;
+use embedded_hal::digital::v2::OutputPin;
+use std::sync::{Arc, Mutex};
+
+/// `N` daisy-chained 74HC595s
+///
+/// Output `i` is bit `i % 8` (Q0..Q7) of chip `i / 8`, chip 0 being the one
+/// wired to the Pi. Changes are buffered until [`write`](Self::write).
+///
+/// # Connections
+///
+/// - DATA = SER (DS) of chip 0, chain Q7' to SER of the next chip
+/// - CLOCK = SRCLK (SH_CP) of every chip
+/// - LATCH = RCLK (ST_CP) of every chip
+///
+/// **NOTE** OE needs to be low and SRCLR high, e.g. tied to GND and VCC
+pub struct HC595<DATA, CLOCK, LATCH, E, const N: usize>
+where
+    DATA: OutputPin<Error = E>,
+    CLOCK: OutputPin<Error = E>,
+    LATCH: OutputPin<Error = E>,
+{
+    data: DATA,
+    clock: CLOCK,
+    latch: LATCH,
+    bytes: [u8; N],
+}
+
+impl<DATA, CLOCK, LATCH, E, const N: usize> HC595<DATA, CLOCK, LATCH, E, N>
+where
+    DATA: OutputPin<Error = E>,
+    CLOCK: OutputPin<Error = E>,
+    LATCH: OutputPin<Error = E>,
+{
+    /// Creates a new `HC595` with every output low
+    pub fn new(mut data: DATA, mut clock: CLOCK, mut latch: LATCH) -> Result<Self, E> {
+        data.set_low()?;
+        clock.set_low()?;
+        latch.set_low()?;
+
+        let mut sr = Self {
+            data,
+            clock,
+            latch,
+            bytes: [0; N],
+        };
+        sr.write()?;
+
+        Ok(sr)
+    }
+
+    /// Number of outputs
+    pub fn outputs(&self) -> usize {
+        N * 8
+    }
+
+    /// # Panics
+    ///
+    /// If `index` is not below [`outputs`](Self::outputs).
+    pub fn set_bit(&mut self, index: usize, high: bool) {
+        let (chip, bit) = (index / 8, index % 8);
+
+        if high {
+            self.bytes[chip] |= 1 << bit;
+        } else {
+            self.bytes[chip] &= !(1 << bit);
+        }
+    }
+
+    pub fn bit(&self, index: usize) -> bool {
+        self.bytes[index / 8] & (1 << (index % 8)) != 0
+    }
+
+    /// Sets all 8 outputs of `chip`, bit 0 is Q0
+    pub fn set_byte(&mut self, chip: usize, byte: u8) {
+        self.bytes[chip] = byte;
+    }
+
+    pub fn byte(&self, chip: usize) -> u8 {
+        self.bytes[chip]
+    }
+
+    pub fn set_bytes(&mut self, bytes: [u8; N]) {
+        self.bytes = bytes;
+    }
+
+    /// Shifts the buffer out and latches it to the outputs
+    pub fn write(&mut self) -> Result<(), E> {
+        // the first byte shifted out ends up in the last chip
+        for i in (0..N).rev() {
+            let byte = self.bytes[i];
+            for bit in (0..8).rev() {
+                if byte & (1 << bit) != 0 {
+                    self.data.set_high()?;
+                } else {
+                    self.data.set_low()?;
+                }
+                self.clock.set_high()?;
+                self.clock.set_low()?;
+            }
+        }
+
+        self.latch.set_high()?;
+        self.latch.set_low()
+    }
+
+    /// Shares the register so each output can be an [`OutputBit`]
+    pub fn into_shared(self) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn release(self) -> (DATA, CLOCK, LATCH) {
+        (self.data, self.clock, self.latch)
+    }
+}
+
+/// One shift register output as an embedded-hal `OutputPin`
+///
+/// Every change is written out immediately.
+pub struct OutputBit<DATA, CLOCK, LATCH, E, const N: usize>
+where
+    DATA: OutputPin<Error = E>,
+    CLOCK: OutputPin<Error = E>,
+    LATCH: OutputPin<Error = E>,
+{
+    sr: Arc<Mutex<HC595<DATA, CLOCK, LATCH, E, N>>>,
+    index: usize,
+}
+
+impl<DATA, CLOCK, LATCH, E, const N: usize> OutputBit<DATA, CLOCK, LATCH, E, N>
+where
+    DATA: OutputPin<Error = E>,
+    CLOCK: OutputPin<Error = E>,
+    LATCH: OutputPin<Error = E>,
+{
+    pub fn new(sr: &Arc<Mutex<HC595<DATA, CLOCK, LATCH, E, N>>>, index: usize) -> Self {
+        assert!(index < N * 8, "output out of range");
+
+        Self {
+            sr: sr.clone(),
+            index,
+        }
+    }
+
+    fn set(&mut self, high: bool) -> Result<(), E> {
+        let mut sr = lock(&self.sr);
+        sr.set_bit(self.index, high);
+        sr.write()
+    }
+}
+
+impl<DATA, CLOCK, LATCH, E, const N: usize> OutputPin for OutputBit<DATA, CLOCK, LATCH, E, N>
+where
+    DATA: OutputPin<Error = E>,
+    CLOCK: OutputPin<Error = E>,
+    LATCH: OutputPin<Error = E>,
+{
+    type Error = E;
+
+    fn set_low(&mut self) -> Result<(), E> {
+        self.set(false)
+    }
+
+    fn set_high(&mut self) -> Result<(), E> {
+        self.set(true)
+    }
+}
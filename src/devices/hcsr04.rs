@@ -0,0 +1,278 @@
+//! HC-SR04 ultrasonic distance sensor, also the US-100 without its jumper
+//!
+//! A 10 us pulse on TRIG sends a burst, ECHO then stays high for as long as
+//! the sound took to come back. The echo is timed either by polling the pin
+//! ([`PollingEcho`]) or from edge interrupts ([`InterruptEcho`]), which
+//! doesn't keep a CPU core busy.
+//!
+//! **NOTE** The HC-SR04's ECHO is 5V, use a divider to bring it to 3.3V
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use std::fmt;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+pub mod interrupt;
+
+pub use interrupt::InterruptEcho;
+
+/// Pause the datasheet asks for between measurements
+const CYCLE: Duration = Duration::from_millis(60);
+/// The burst takes about 0.5 ms before ECHO goes high
+const START_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// Speed of sound in air in m/s at `celsius`
+pub fn speed_of_sound(celsius: f64) -> f64 {
+    331.3 + 0.606 * celsius
+}
+
+/// Outcome of waiting for an echo pulse
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Pulse {
+    Width(Duration),
+    /// ECHO never went high
+    NotStarted,
+    /// ECHO stayed high past the timeout
+    NotEnded,
+}
+
+/// Times the ECHO pin
+pub trait Echo {
+    type Error;
+
+    /// Called right before triggering, e.g. to forget old edges
+    fn arm(&mut self) -> Result<(), Self::Error>;
+
+    /// Waits for ECHO to go high within `start` and low again within `end`
+    fn pulse(&mut self, start: Duration, end: Duration) -> Result<Pulse, Self::Error>;
+}
+
+/// Busy-waits on any `InputPin`
+pub struct PollingEcho<P>(pub P);
+
+impl<P, E> Echo for PollingEcho<P>
+where
+    P: InputPin<Error = E>,
+{
+    type Error = E;
+
+    fn arm(&mut self) -> Result<(), E> {
+        Ok(())
+    }
+
+    fn pulse(&mut self, start: Duration, end: Duration) -> Result<Pulse, E> {
+        let triggered = Instant::now();
+        while self.0.is_low()? {
+            if triggered.elapsed() > start {
+                return Ok(Pulse::NotStarted);
+            }
+        }
+
+        let rose = Instant::now();
+        while self.0.is_high()? {
+            if rose.elapsed() > end {
+                return Ok(Pulse::NotEnded);
+            }
+        }
+
+        Ok(Pulse::Width(rose.elapsed()))
+    }
+}
+
+#[derive(Debug)]
+pub enum Error<E> {
+    Pin(E),
+    /// The sensor didn't answer, check wiring and power
+    Timeout,
+    /// Nothing between the minimum and maximum distance
+    OutOfRange,
+    /// The temperature or distance range is not [valid](Config::is_valid)
+    InvalidConfig,
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Pin(e) => write!(f, "pin error: {:?}", e),
+            Error::Timeout => write!(f, "no echo from the sensor"),
+            Error::OutOfRange => write!(f, "distance out of range"),
+            Error::InvalidConfig => write!(f, "invalid configuration"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// Air temperature, for the speed of sound
+    pub celsius: f64,
+    /// Closest reliable distance in meters
+    pub min_distance: f64,
+    /// Furthest distance in meters, echoes from further away are ignored
+    pub max_distance: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            celsius: 20.0,
+            min_distance: 0.02,
+            max_distance: 4.0,
+        }
+    }
+}
+
+impl Config {
+    /// The temperature is above absolute zero and the distances are finite,
+    /// with `0 <= min_distance < max_distance`
+    pub fn is_valid(&self) -> bool {
+        self.celsius.is_finite()
+            && self.celsius > -273.15
+            && self.min_distance >= 0.0
+            && self.min_distance < self.max_distance
+            && self.max_distance.is_finite()
+    }
+}
+
+pub struct HCSR04<TRIG, ECHO> {
+    trig: TRIG,
+    echo: ECHO,
+    config: Config,
+    last: Option<Instant>,
+}
+
+impl<TRIG, ECHO, E> HCSR04<TRIG, ECHO>
+where
+    TRIG: OutputPin<Error = E>,
+    ECHO: Echo<Error = E>,
+{
+    pub fn new(mut trig: TRIG, echo: ECHO, config: Config) -> Result<Self, Error<E>> {
+        if !config.is_valid() {
+            return Err(Error::InvalidConfig);
+        }
+        trig.set_low().map_err(Error::Pin)?;
+
+        Ok(Self {
+            trig,
+            echo,
+            config,
+            last: None,
+        })
+    }
+
+    /// Updates the temperature used for the speed of sound, an invalid one
+    /// is rejected and the old one kept
+    pub fn set_temperature(&mut self, celsius: f64) -> Result<(), Error<E>> {
+        let config = Config {
+            celsius,
+            ..self.config
+        };
+        if !config.is_valid() {
+            return Err(Error::InvalidConfig);
+        }
+        self.config = config;
+
+        Ok(())
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Measures the distance in meters
+    ///
+    /// Waits first if the last measurement was less than 60 ms ago, so
+    /// late echoes don't mix in.
+    pub fn measure(&mut self) -> Result<f64, Error<E>> {
+        if let Some(last) = self.last {
+            if let Some(wait) = CYCLE.checked_sub(last.elapsed()) {
+                sleep(wait);
+            }
+        }
+        self.last = Some(Instant::now());
+
+        let speed = speed_of_sound(self.config.celsius);
+        // round trip to max distance, with some slack
+        let max_pulse = Duration::try_from_secs_f64(self.config.max_distance * 2.0 / speed * 1.2)
+            .map_err(|_| Error::InvalidConfig)?;
+
+        self.echo.arm().map_err(Error::Pin)?;
+        self.trig.set_high().map_err(Error::Pin)?;
+        sleep(Duration::from_micros(10));
+        self.trig.set_low().map_err(Error::Pin)?;
+
+        let width = match self
+            .echo
+            .pulse(START_TIMEOUT, max_pulse)
+            .map_err(Error::Pin)?
+        {
+            Pulse::Width(width) => width,
+            Pulse::NotStarted => return Err(Error::Timeout),
+            Pulse::NotEnded => return Err(Error::OutOfRange),
+        };
+
+        let distance = width.as_secs_f64() * speed / 2.0;
+        if distance < self.config.min_distance || distance > self.config.max_distance {
+            return Err(Error::OutOfRange);
+        }
+
+        Ok(distance)
+    }
+
+    /// Median of `samples` measurements, skipping failed ones
+    ///
+    /// Returns the last error when every measurement failed.
+    pub fn measure_median(&mut self, samples: usize) -> Result<f64, Error<E>> {
+        let mut distances = Vec::with_capacity(samples);
+        let mut error = Error::OutOfRange;
+
+        for _ in 0..samples.max(1) {
+            match self.measure() {
+                Ok(distance) => distances.push(distance),
+                Err(Error::Pin(e)) => return Err(Error::Pin(e)),
+                Err(e) => error = e,
+            }
+        }
+
+        if distances.is_empty() {
+            return Err(error);
+        }
+
+        distances.sort_by(|a, b| a.total_cmp(b));
+        let mid = distances.len() / 2;
+        if distances.len() % 2 == 0 {
+            Ok((distances[mid - 1] + distances[mid]) / 2.0)
+        } else {
+            Ok(distances[mid])
+        }
+    }
+
+    pub fn release(self) -> (TRIG, ECHO) {
+        (self.trig, self.echo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_config() {
+        let config = Config::default();
+        assert!(config.is_valid());
+
+        for celsius in [f64::NAN, f64::INFINITY, -300.0] {
+            assert!(!Config { celsius, ..config }.is_valid());
+        }
+        for max_distance in [f64::NAN, f64::INFINITY, -1.0, 0.01] {
+            assert!(!Config {
+                max_distance,
+                ..config
+            }
+            .is_valid());
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Echo timing from rppal's GPIO interrupts
+
+use super::{Echo, Pulse};
+use crate::utils::edges::Edges;
+use rppal::gpio::{self, InputPin, Level};
+use std::convert::Infallible;
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+/// Timestamps both edges of ECHO from an interrupt callback
+///
+/// The timestamps are taken on rppal's interrupt thread, so scheduling
+/// latency adds a few millimeters of jitter, less than polling under load.
+pub struct InterruptEcho {
+    edges: Edges,
+}
+
+impl InterruptEcho {
+    pub fn new(pin: InputPin) -> gpio::Result<Self> {
+        Ok(Self {
+            edges: Edges::new(pin)?,
+        })
+    }
+
+    pub fn release(self) -> gpio::Result<InputPin> {
+        self.edges.release()
+    }
+
+    /// Waits until the edge to `level`, returns when it happened
+    fn wait_for(&self, level: Level, until: Instant) -> Option<Instant> {
+        loop {
+            let timeout = until.saturating_duration_since(Instant::now());
+            match self.edges.recv_timeout(timeout) {
+                Ok((l, at)) if l == level => return Some(at),
+                Ok(_) => {}
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => {
+                    return None
+                }
+            }
+        }
+    }
+}
+
+impl Echo for InterruptEcho {
+    type Error = Infallible;
+
+    fn arm(&mut self) -> Result<(), Infallible> {
+        for _ in self.edges.try_iter() {}
+        Ok(())
+    }
+
+    fn pulse(&mut self, start: Duration, end: Duration) -> Result<Pulse, Infallible> {
+        let rose = match self.wait_for(Level::High, Instant::now() + start) {
+            Some(at) => at,
+            None => return Ok(Pulse::NotStarted),
+        };
+
+        match self.wait_for(Level::Low, rose + end) {
+            Some(fell) => Ok(Pulse::Width(fell - rose)),
+            None => Ok(Pulse::NotEnded),
+        }
+    }
+}
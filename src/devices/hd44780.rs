@@ -0,0 +1,272 @@
+//! HD44780 character LCD on a PCF8574 I2C backpack, 4-bit mode
+//!
+//! The usual backpack wiring is P0 = RS, P1 = RW, P2 = EN, P3 = backlight and
+//! P4..P7 = D4..D7.
+
+use embedded_hal::blocking::i2c::Write;
+use std::fmt;
+use std::{thread::sleep, time::Duration};
+
+pub mod buffered;
+
+/// PCF8574, the PCF8574A variant is at `0x3f`
+pub const DEFAULT_ADDR: u8 = 0x27;
+
+const RS: u8 = 1 << 0;
+const EN: u8 = 1 << 2;
+const BACKLIGHT: u8 = 1 << 3;
+
+const CLEAR: u8 = 0x01;
+const HOME: u8 = 0x02;
+const ENTRY_MODE: u8 = 0x04;
+const DISPLAY_CONTROL: u8 = 0x08;
+const SHIFT: u8 = 0x10;
+const FUNCTION_SET: u8 = 0x20;
+const SET_CGRAM_ADDR: u8 = 0x40;
+const SET_DDRAM_ADDR: u8 = 0x80;
+
+const ENTRY_INCREMENT: u8 = 1 << 1;
+const DISPLAY_ON: u8 = 1 << 2;
+const CURSOR_ON: u8 = 1 << 1;
+const BLINK_ON: u8 = 1 << 0;
+const SHIFT_DISPLAY: u8 = 1 << 3;
+const SHIFT_RIGHT: u8 = 1 << 2;
+const TWO_LINES: u8 = 1 << 3;
+
+/// DDRAM address of each row's first column, rows 2 and 3 are on 20x4s
+const ROW_OFFSETS: [u8; 4] = [0x00, 0x40, 0x14, 0x54];
+
+/// Clear and home take up to 1.52 ms, other commands 37 us which an I2C
+/// write at 100 kHz already exceeds
+const LONG_COMMAND: Duration = Duration::from_micros(2000);
+
+/// Display pattern of a character, unsupported ones show as `?`
+///
+/// `'\u{0}'..='\u{7}'` are the custom characters.
+pub fn char_code(c: char) -> u8 {
+    match c {
+        '\u{0}'..='\u{7}' | ' '..='}' => c as u8,
+        '°' => 0xdf,
+        'µ' => 0xe4,
+        '→' => 0x7e,
+        '←' => 0x7f,
+        '█' => 0xff,
+        _ => b'?',
+    }
+}
+
+/// A 1602, 2004 or similar LCD
+///
+/// Text written past the end of a row continues on the next one and `'\n'`
+/// starts a new row.
+pub struct HD44780<I2C> {
+    i2c: I2C,
+    addr: u8,
+    cols: u8,
+    rows: u8,
+    backlight: u8,
+    control: u8,
+    col: u8,
+    row: u8,
+}
+
+impl<I2C, E> HD44780<I2C>
+where
+    I2C: Write<Error = E>,
+{
+    /// Creates a new `HD44780`, cleared, with the backlight on and the
+    /// cursor hidden
+    ///
+    /// # Panics
+    ///
+    /// If there are no columns, more than the controller's 40, or not 1 to
+    /// 4 rows.
+    pub fn new(i2c: I2C, addr: u8, cols: u8, rows: u8) -> Result<Self, E> {
+        assert!((1..=40).contains(&cols), "1 to 40 columns");
+        assert!((1..=4).contains(&rows), "1 to 4 rows");
+
+        let mut lcd = Self {
+            i2c,
+            addr,
+            cols,
+            rows,
+            backlight: BACKLIGHT,
+            control: DISPLAY_ON,
+            col: 0,
+            row: 0,
+        };
+
+        // power on wait, then the datasheet's reset by instruction sequence
+        // which gets into 4-bit mode from any state
+        sleep(Duration::from_millis(50));
+        lcd.write_nibble(0x03, 0)?;
+        sleep(Duration::from_micros(4500));
+        lcd.write_nibble(0x03, 0)?;
+        sleep(Duration::from_micros(150));
+        lcd.write_nibble(0x03, 0)?;
+        lcd.write_nibble(0x02, 0)?;
+
+        let lines = if rows > 1 { TWO_LINES } else { 0 };
+        lcd.command(FUNCTION_SET | lines)?;
+        lcd.command(DISPLAY_CONTROL | lcd.control)?;
+        lcd.command(ENTRY_MODE | ENTRY_INCREMENT)?;
+        lcd.clear()?;
+
+        Ok(lcd)
+    }
+
+    pub fn cols(&self) -> u8 {
+        self.cols
+    }
+
+    pub fn rows(&self) -> u8 {
+        self.rows
+    }
+
+    /// Blanks the display and moves the cursor home
+    pub fn clear(&mut self) -> Result<(), E> {
+        self.command(CLEAR)?;
+        sleep(LONG_COMMAND);
+        self.col = 0;
+        self.row = 0;
+        Ok(())
+    }
+
+    /// Moves the cursor home and undoes any display shift
+    pub fn home(&mut self) -> Result<(), E> {
+        self.command(HOME)?;
+        sleep(LONG_COMMAND);
+        self.col = 0;
+        self.row = 0;
+        Ok(())
+    }
+
+    /// Out of range positions are clamped
+    pub fn set_cursor(&mut self, col: u8, row: u8) -> Result<(), E> {
+        self.col = col.min(self.cols - 1);
+        self.row = row.min(self.rows - 1);
+        self.command(SET_DDRAM_ADDR | self.address())
+    }
+
+    pub fn cursor(&self) -> (u8, u8) {
+        (self.col, self.row)
+    }
+
+    /// Shows an underline cursor
+    pub fn show_cursor(&mut self, on: bool) -> Result<(), E> {
+        self.set_control(CURSOR_ON, on)
+    }
+
+    /// Blinks the character at the cursor
+    pub fn blink(&mut self, on: bool) -> Result<(), E> {
+        self.set_control(BLINK_ON, on)
+    }
+
+    /// Turns the display on or off, contents are kept
+    pub fn set_display(&mut self, on: bool) -> Result<(), E> {
+        self.set_control(DISPLAY_ON, on)
+    }
+
+    pub fn set_backlight(&mut self, on: bool) -> Result<(), E> {
+        self.backlight = if on { BACKLIGHT } else { 0 };
+        self.i2c.write(self.addr, &[self.backlight])
+    }
+
+    pub fn backlight(&self) -> bool {
+        self.backlight != 0
+    }
+
+    /// Shifts the whole display one column left without changing DDRAM
+    pub fn scroll_left(&mut self) -> Result<(), E> {
+        self.command(SHIFT | SHIFT_DISPLAY)
+    }
+
+    pub fn scroll_right(&mut self) -> Result<(), E> {
+        self.command(SHIFT | SHIFT_DISPLAY | SHIFT_RIGHT)
+    }
+
+    /// Defines custom character `location` (0..=7) from 8 rows of 5 pixels,
+    /// bit 4 being the leftmost
+    ///
+    /// Shown with `'\u{0}'..='\u{7}'`. The cursor position is restored.
+    pub fn create_char(&mut self, location: u8, rows: [u8; 8]) -> Result<(), E> {
+        assert!(location < 8, "location out of range");
+
+        self.command(SET_CGRAM_ADDR | (location << 3))?;
+        for row in rows.iter() {
+            self.write_data(row & 0x1f)?;
+        }
+
+        self.command(SET_DDRAM_ADDR | self.address())
+    }
+
+    /// Writes a character at the cursor
+    pub fn write_char(&mut self, c: char) -> Result<(), E> {
+        if c == '\n' {
+            return self.set_cursor(0, (self.row + 1) % self.rows);
+        }
+
+        if self.col >= self.cols {
+            self.set_cursor(0, (self.row + 1) % self.rows)?;
+        }
+        self.write_data(char_code(c))?;
+        self.col += 1;
+
+        Ok(())
+    }
+
+    pub fn write_str(&mut self, s: &str) -> Result<(), E> {
+        for c in s.chars() {
+            self.write_char(c)?;
+        }
+        Ok(())
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn address(&self) -> u8 {
+        ROW_OFFSETS[usize::from(self.row)] + self.col
+    }
+
+    fn set_control(&mut self, flag: u8, on: bool) -> Result<(), E> {
+        if on {
+            self.control |= flag;
+        } else {
+            self.control &= !flag;
+        }
+        self.command(DISPLAY_CONTROL | self.control)
+    }
+
+    fn command(&mut self, command: u8) -> Result<(), E> {
+        self.write_byte(command, 0)
+    }
+
+    fn write_data(&mut self, data: u8) -> Result<(), E> {
+        self.write_byte(data, RS)
+    }
+
+    /// Sends both nibbles in one transfer, each I2C byte takes long enough
+    /// to meet the enable pulse timing
+    fn write_byte(&mut self, byte: u8, mode: u8) -> Result<(), E> {
+        let high = (byte & 0xf0) | mode | self.backlight;
+        let low = (byte << 4) | mode | self.backlight;
+
+        self.i2c.write(self.addr, &[high | EN, high, low | EN, low])
+    }
+
+    fn write_nibble(&mut self, nibble: u8, mode: u8) -> Result<(), E> {
+        let bits = (nibble << 4) | mode | self.backlight;
+        self.i2c.write(self.addr, &[bits | EN, bits])
+    }
+}
+
+impl<I2C, E> fmt::Write for HD44780<I2C>
+where
+    I2C: Write<Error = E>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        HD44780::write_str(self, s).map_err(|_| fmt::Error)
+    }
+}
@@ -0,0 +1,155 @@
+//! Frame buffered LCD updates
+
+use super::{char_code, HD44780};
+use embedded_hal::blocking::i2c::Write;
+use std::fmt;
+
+/// An [`HD44780`] drawn from a frame buffer
+///
+/// Writing only touches the buffer. [`update`](Self::update) then sends a
+/// limited number of changed characters per call, so a main loop can
+/// refresh the display a bit at a time without stalling on the bus.
+pub struct BufferedLcd<I2C> {
+    lcd: HD44780<I2C>,
+    frame: Vec<u8>,
+    /// What the display shows, `None` when unknown
+    shown: Vec<Option<u8>>,
+    col: u8,
+    row: u8,
+}
+
+impl<I2C, E> BufferedLcd<I2C>
+where
+    I2C: Write<Error = E>,
+{
+    /// Takes over `lcd`, the next update redraws every character
+    pub fn new(lcd: HD44780<I2C>) -> Self {
+        let size = usize::from(lcd.cols()) * usize::from(lcd.rows());
+
+        Self {
+            lcd,
+            frame: vec![b' '; size],
+            shown: vec![None; size],
+            col: 0,
+            row: 0,
+        }
+    }
+
+    /// Blanks the buffer and moves the write position home
+    pub fn clear(&mut self) {
+        self.frame.fill(b' ');
+        self.col = 0;
+        self.row = 0;
+    }
+
+    /// Out of range positions are clamped
+    pub fn set_cursor(&mut self, col: u8, row: u8) {
+        self.col = col.min(self.lcd.cols() - 1);
+        self.row = row.min(self.lcd.rows() - 1);
+    }
+
+    /// Writes a character at the write position, wrapping like
+    /// [`HD44780::write_char`]
+    pub fn write_char(&mut self, c: char) {
+        if c == '\n' || self.col >= self.lcd.cols() {
+            self.col = 0;
+            self.row = (self.row + 1) % self.lcd.rows();
+        }
+        if c == '\n' {
+            return;
+        }
+
+        let i = self.index(self.col, self.row);
+        self.frame[i] = char_code(c);
+        self.col += 1;
+    }
+
+    /// Replaces a whole row, padded with spaces or cut to fit
+    pub fn set_line(&mut self, row: u8, text: &str) {
+        let cols = usize::from(self.lcd.cols());
+        let start = self.index(0, row.min(self.lcd.rows() - 1));
+        let mut chars = text.chars();
+
+        for cell in self.frame[start..start + cols].iter_mut() {
+            *cell = chars.next().map_or(b' ', char_code);
+        }
+    }
+
+    /// Whether the display matches the buffer
+    pub fn is_synced(&self) -> bool {
+        self.frame
+            .iter()
+            .zip(self.shown.iter())
+            .all(|(f, s)| Some(*f) == *s)
+    }
+
+    /// Sends up to `max_chars` changed characters, returns whether the
+    /// display is now in sync
+    pub fn update(&mut self, max_chars: usize) -> Result<bool, E> {
+        let cols = usize::from(self.lcd.cols());
+        let mut sent = 0;
+        // where the display's cursor is, writes after the first on the same
+        // row don't need a new address
+        let mut next = None;
+
+        for i in 0..self.frame.len() {
+            if Some(self.frame[i]) == self.shown[i] {
+                continue;
+            }
+            if sent == max_chars {
+                return Ok(false);
+            }
+
+            if next != Some(i) {
+                self.lcd.set_cursor((i % cols) as u8, (i / cols) as u8)?;
+            }
+            self.lcd.write_data(self.frame[i])?;
+            self.shown[i] = Some(self.frame[i]);
+
+            sent += 1;
+            next = if (i + 1) % cols == 0 {
+                None
+            } else {
+                Some(i + 1)
+            };
+        }
+
+        Ok(true)
+    }
+
+    /// Sends every changed character
+    pub fn flush(&mut self) -> Result<(), E> {
+        self.update(usize::MAX).map(|_| ())
+    }
+
+    /// Forgets what the display shows so the next update redraws it all,
+    /// e.g. after using the LCD directly
+    pub fn invalidate(&mut self) {
+        self.shown.fill(None);
+    }
+
+    /// For cursor, backlight and custom character control
+    pub fn lcd_mut(&mut self) -> &mut HD44780<I2C> {
+        &mut self.lcd
+    }
+
+    pub fn release(self) -> HD44780<I2C> {
+        self.lcd
+    }
+
+    fn index(&self, col: u8, row: u8) -> usize {
+        usize::from(row) * usize::from(self.lcd.cols()) + usize::from(col)
+    }
+}
+
+impl<I2C, E> fmt::Write for BufferedLcd<I2C>
+where
+    I2C: Write<Error = E>,
+{
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.write_char(c);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,409 @@
+//! Infrared remote decoding, NEC, NEC extended and RC5
+//!
+//! Demodulating receivers such as the VS1838B or TSOP38238 pull their
+//! output low while they see the 38 kHz carrier (a mark) and leave it high
+//! otherwise (a space). [`IrDecoder`] turns the timestamps of those edges
+//! into [`Frame`]s, [`IrReceiver`] collects the edges from rppal's
+//! interrupts.
+
+use std::time::{Duration, Instant};
+
+pub mod receiver;
+pub mod remote;
+
+pub use receiver::IrReceiver;
+
+/// Spaces longer than this end a frame
+const FRAME_GAP: Duration = Duration::from_millis(10);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// `address` is 8 bits unless `extended`
+    Nec {
+        address: u16,
+        command: u8,
+        extended: bool,
+    },
+    /// Sent every 108 ms while an NEC key is held
+    NecRepeat,
+    /// `toggle` flips on every new press, repeats of a held key keep it
+    Rc5 {
+        address: u8,
+        command: u8,
+        toggle: bool,
+    },
+}
+
+/// Whether `width` is within 30% of `us` microseconds
+fn near(width: Duration, us: u64) -> bool {
+    let width = width.as_micros() as u64;
+    let tolerance = us * 3 / 10;
+    width + tolerance >= us && width <= us + tolerance
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum NecState {
+    Idle,
+    LeaderSpace,
+    BitMark(u8, u32),
+    BitSpace(u8, u32),
+    Trailer(u32),
+    RepeatTrailer,
+}
+
+/// 9 ms mark, 4.5 ms space, 32 bits LSB first as 562 us marks followed by
+/// 562 us (0) or 1687 us (1) spaces, then a final mark
+struct Nec {
+    state: NecState,
+}
+
+impl Nec {
+    fn pulse(&mut self, mark: bool, width: Duration) -> Option<Frame> {
+        let (state, frame) = match (self.state, mark) {
+            (NecState::LeaderSpace, false) if near(width, 4500) => (NecState::BitMark(0, 0), None),
+            (NecState::LeaderSpace, false) if near(width, 2250) => (NecState::RepeatTrailer, None),
+            (NecState::BitMark(n, data), true) if near(width, 562) => {
+                (NecState::BitSpace(n, data), None)
+            }
+            (NecState::BitSpace(n, data), false) if near(width, 562) || near(width, 1687) => {
+                let data = if near(width, 1687) {
+                    data | 1 << n
+                } else {
+                    data
+                };
+                if n == 31 {
+                    (NecState::Trailer(data), None)
+                } else {
+                    (NecState::BitMark(n + 1, data), None)
+                }
+            }
+            (NecState::Trailer(data), true) if near(width, 562) => {
+                (NecState::Idle, Self::frame(data))
+            }
+            (NecState::RepeatTrailer, true) if near(width, 562) => {
+                (NecState::Idle, Some(Frame::NecRepeat))
+            }
+            // anything unexpected may still be the start of a new frame
+            (_, true) if near(width, 9000) => (NecState::LeaderSpace, None),
+            _ => (NecState::Idle, None),
+        };
+
+        self.state = state;
+        frame
+    }
+
+    fn frame(data: u32) -> Option<Frame> {
+        let [addr_lo, addr_hi, command, command_inv] = data.to_le_bytes();
+        if command != !command_inv {
+            return None;
+        }
+
+        // extended NEC uses the inverted address byte for more addresses
+        let extended = addr_lo != !addr_hi;
+        let address = if extended {
+            u16::from_le_bytes([addr_lo, addr_hi])
+        } else {
+            u16::from(addr_lo)
+        };
+
+        Some(Frame::Nec {
+            address,
+            command,
+            extended,
+        })
+    }
+}
+
+/// Manchester coded with 889 us half bits, 14 bits MSB first: two start
+/// bits, toggle, 5 address and 6 command bits
+///
+/// A 1 is a space then a mark. The second start bit is the inverted 7th
+/// command bit in RC5X.
+struct Rc5 {
+    /// Half bits received so far, true for a mark
+    halves: Vec<bool>,
+}
+
+impl Rc5 {
+    const HALF: u64 = 889;
+    const HALVES: usize = 28;
+
+    fn pulse(&mut self, mark: bool, width: Duration) -> Option<Frame> {
+        let n = if near(width, Self::HALF) {
+            1
+        } else if near(width, 2 * Self::HALF) {
+            2
+        } else {
+            // a long space ends the frame, a 0 as the last bit has its
+            // trailing space merged into it
+            if !mark && self.halves.len() == Self::HALVES - 1 {
+                self.halves.push(false);
+                return self.finish();
+            }
+            self.halves.clear();
+            return None;
+        };
+
+        if self.halves.is_empty() {
+            if !mark {
+                return None;
+            }
+            // the space of the first start bit is the idle line
+            self.halves.push(false);
+        }
+
+        self.halves.extend(std::iter::repeat_n(mark, n));
+
+        match self.halves.len() {
+            len if len == Self::HALVES => self.finish(),
+            len if len > Self::HALVES => {
+                self.halves.clear();
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn finish(&mut self) -> Option<Frame> {
+        let mut bits: u16 = 0;
+        for pair in self.halves.chunks(2) {
+            bits <<= 1;
+            match pair {
+                [false, true] => bits |= 1,
+                [true, false] => {}
+                _ => {
+                    self.halves.clear();
+                    return None;
+                }
+            }
+        }
+        self.halves.clear();
+
+        if bits >> 13 != 1 {
+            return None;
+        }
+
+        let field = (bits >> 12) & 1;
+        Some(Frame::Rc5 {
+            toggle: (bits >> 11) & 1 == 1,
+            address: ((bits >> 6) & 0x1f) as u8,
+            command: (bits & 0x3f) as u8 | if field == 0 { 0x40 } else { 0 },
+        })
+    }
+}
+
+/// Decodes every supported protocol from receiver edges
+pub struct IrDecoder {
+    nec: Nec,
+    rc5: Rc5,
+    /// Current level, true for a mark, and since when
+    level: Option<(bool, Instant)>,
+    idle_handled: bool,
+}
+
+impl Default for IrDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IrDecoder {
+    pub fn new() -> Self {
+        Self {
+            nec: Nec {
+                state: NecState::Idle,
+            },
+            rc5: Rc5 {
+                halves: Vec::with_capacity(Rc5::HALVES),
+            },
+            level: None,
+            idle_handled: true,
+        }
+    }
+
+    /// Feeds an edge, `mark` being the new level (receiver output low)
+    pub fn edge(&mut self, mark: bool, at: Instant) -> Option<Frame> {
+        let previous = self.level.replace((mark, at));
+        self.idle_handled = false;
+
+        match previous {
+            Some((level, since)) if level != mark => {
+                let width = at.saturating_duration_since(since);
+                self.pulse(level, width)
+            }
+            _ => None,
+        }
+    }
+
+    /// Ends a frame when the line has been idle for a while, call this
+    /// regularly
+    ///
+    /// Some frames can't be told complete until the space after them is
+    /// long enough.
+    pub fn idle(&mut self, now: Instant) -> Option<Frame> {
+        match self.level {
+            Some((false, since))
+                if !self.idle_handled && now.saturating_duration_since(since) > FRAME_GAP =>
+            {
+                self.idle_handled = true;
+                self.pulse(false, now.saturating_duration_since(since))
+            }
+            _ => None,
+        }
+    }
+
+    fn pulse(&mut self, mark: bool, width: Duration) -> Option<Frame> {
+        let nec = self.nec.pulse(mark, width);
+        let rc5 = self.rc5.pulse(mark, width);
+        nec.or(rc5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds alternating mark and space widths in microseconds, starting
+    /// with a mark, then lets the line go idle
+    fn decode(pulses: &[u64]) -> Vec<Frame> {
+        let mut decoder = IrDecoder::new();
+        let mut at = Instant::now();
+        let mut frames: Vec<Frame> = decoder.edge(true, at).into_iter().collect();
+
+        for (i, width) in pulses.iter().enumerate() {
+            at += Duration::from_micros(*width);
+            frames.extend(decoder.edge(i % 2 == 1, at));
+        }
+        frames.extend(decoder.idle(at + 2 * FRAME_GAP));
+
+        frames
+    }
+
+    fn nec(data: u32) -> Vec<u64> {
+        let mut pulses = vec![9000, 4500];
+        for n in 0..32 {
+            pulses.push(562);
+            pulses.push(if data & 1 << n != 0 { 1687 } else { 562 });
+        }
+        pulses.push(562);
+        pulses
+    }
+
+    fn nec_data(address: u8, command: u8) -> u32 {
+        u32::from_le_bytes([address, !address, command, !command])
+    }
+
+    /// Manchester halves to pulse widths, the leading and trailing spaces
+    /// being the idle line
+    fn rc5(toggle: bool, address: u8, command: u8) -> Vec<u64> {
+        let bits =
+            0b11 << 12 | u16::from(toggle) << 11 | u16::from(address) << 6 | u16::from(command);
+        let halves: Vec<bool> = (0..14)
+            .rev()
+            .flat_map(|i| {
+                let one = bits & 1 << i != 0;
+                [!one, one]
+            })
+            .collect();
+
+        let mut pulses: Vec<(bool, u64)> = Vec::new();
+        for half in halves {
+            match pulses.last_mut() {
+                Some((mark, width)) if *mark == half => *width += 889,
+                _ => pulses.push((half, 889)),
+            }
+        }
+        pulses.remove(0);
+        if pulses.last().is_some_and(|(mark, _)| !mark) {
+            pulses.pop();
+        }
+
+        pulses.into_iter().map(|(_, width)| width).collect()
+    }
+
+    #[test]
+    fn nec_frame() {
+        assert_eq!(
+            decode(&nec(nec_data(0x00, 0x18))),
+            [Frame::Nec {
+                address: 0x00,
+                command: 0x18,
+                extended: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn nec_extended_address() {
+        let data = u32::from_le_bytes([0x34, 0x12, 0x5a, !0x5a]);
+
+        assert_eq!(
+            decode(&nec(data)),
+            [Frame::Nec {
+                address: 0x1234,
+                command: 0x5a,
+                extended: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn nec_bad_checksum() {
+        let data = nec_data(0x00, 0x18) ^ 1 << 24;
+        assert_eq!(decode(&nec(data)), []);
+    }
+
+    #[test]
+    fn nec_repeat() {
+        assert_eq!(decode(&[9000, 2250, 562]), [Frame::NecRepeat]);
+    }
+
+    #[test]
+    fn nec_timing_tolerance() {
+        // receivers stretch marks and shorten spaces by around 100 us
+        let pulses: Vec<u64> = nec(nec_data(0x07, 0x42))
+            .iter()
+            .enumerate()
+            .map(|(i, w)| if i % 2 == 0 { w + 100 } else { w - 100 })
+            .collect();
+
+        assert_eq!(
+            decode(&pulses),
+            [Frame::Nec {
+                address: 0x07,
+                command: 0x42,
+                extended: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn rc5_frame_ending_in_one() {
+        assert_eq!(
+            decode(&rc5(true, 0x05, 0x01)),
+            [Frame::Rc5 {
+                address: 0x05,
+                command: 0x01,
+                toggle: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn rc5_frame_ending_in_zero() {
+        // the last space only ends once the line has been idle
+        assert_eq!(
+            decode(&rc5(false, 0x00, 0x0c)),
+            [Frame::Rc5 {
+                address: 0x00,
+                command: 0x0c,
+                toggle: false,
+            }]
+        );
+    }
+
+    #[test]
+    fn noise() {
+        assert_eq!(decode(&[300, 7000, 1200, 450, 3000]), []);
+    }
+}
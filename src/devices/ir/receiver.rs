@@ -0,0 +1,42 @@
+//! IR receiver on an rppal GPIO input
+
+use super::{Frame, IrDecoder};
+use crate::utils::edges::Edges;
+use rppal::gpio::{self, InputPin, Level};
+use std::time::Instant;
+
+/// Timestamps the receiver's edges from an interrupt callback and decodes
+/// them on [`poll`](Self::poll)
+pub struct IrReceiver {
+    edges: Edges,
+    decoder: IrDecoder,
+}
+
+impl IrReceiver {
+    /// `pin` is the receiver's output, most modules have a pull-up already
+    pub fn new(pin: InputPin) -> gpio::Result<Self> {
+        Ok(Self {
+            edges: Edges::new(pin)?,
+            decoder: IrDecoder::new(),
+        })
+    }
+
+    /// Decodes the edges received since the last call
+    ///
+    /// Call at least every 10 ms or so, frames ending in a space are only
+    /// complete once the line has been idle for a while.
+    pub fn poll(&mut self, now: Instant) -> Vec<Frame> {
+        let mut frames = Vec::new();
+
+        for (level, at) in self.edges.try_iter() {
+            frames.extend(self.decoder.edge(level == Level::Low, at));
+        }
+        frames.extend(self.decoder.idle(now));
+
+        frames
+    }
+
+    pub fn release(self) -> gpio::Result<InputPin> {
+        self.edges.release()
+    }
+}
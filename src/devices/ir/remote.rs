@@ -0,0 +1,281 @@
+//! Remote control keys mapped to application actions
+//!
+//! Frames only say that a key is down. [`Remote`] turns them into press and
+//! release events, using NEC repeat codes and the RC5 toggle bit to tell a
+//! held key from a new press.
+
+use super::Frame;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// NEC and RC5 remotes repeat about every 110 ms while a key is held
+pub const HOLD_TIMEOUT: Duration = Duration::from_millis(200);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Key {
+    Nec { address: u16, command: u8 },
+    Rc5 { address: u8, command: u8 },
+}
+
+impl Key {
+    /// The key of a frame, `None` for NEC repeat codes
+    pub fn from_frame(frame: Frame) -> Option<Self> {
+        match frame {
+            Frame::Nec {
+                address, command, ..
+            } => Some(Key::Nec { address, command }),
+            Frame::Rc5 {
+                address, command, ..
+            } => Some(Key::Rc5 { address, command }),
+            Frame::NecRepeat => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyEvent<A> {
+    Pressed(A),
+    Released(A),
+}
+
+struct Held {
+    key: Key,
+    toggle: Option<bool>,
+    seen: Instant,
+}
+
+/// Maps keys to actions of type `A` and tracks the held key
+pub struct Remote<A> {
+    keymap: HashMap<Key, A>,
+    hold_timeout: Duration,
+    held: Option<Held>,
+    events: VecDeque<KeyEvent<A>>,
+}
+
+impl<A: Copy> Default for Remote<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: Copy> Remote<A> {
+    pub fn new() -> Self {
+        Self {
+            keymap: HashMap::new(),
+            hold_timeout: HOLD_TIMEOUT,
+            held: None,
+            events: VecDeque::new(),
+        }
+    }
+
+    pub fn bind(&mut self, key: Key, action: A) {
+        self.keymap.insert(key, action);
+    }
+
+    /// How long after the last frame a key counts as released
+    pub fn set_hold_timeout(&mut self, timeout: Duration) {
+        self.hold_timeout = timeout;
+    }
+
+    /// The action of the key held down, if it is bound
+    pub fn held(&self) -> Option<A> {
+        self.held
+            .as_ref()
+            .and_then(|held| self.keymap.get(&held.key).copied())
+    }
+
+    pub fn feed(&mut self, frame: Frame, now: Instant) {
+        let toggle = match frame {
+            Frame::Rc5 { toggle, .. } => Some(toggle),
+            _ => None,
+        };
+
+        let key = match Key::from_frame(frame) {
+            Some(key) => key,
+            None => {
+                // a repeat code keeps the NEC key held
+                if let Some(held) = self.held.as_mut() {
+                    if let Key::Nec { .. } = held.key {
+                        held.seen = now;
+                    }
+                }
+                return;
+            }
+        };
+
+        if let Some(held) = self.held.as_mut() {
+            if held.key == key && held.toggle == toggle {
+                held.seen = now;
+                return;
+            }
+        }
+
+        self.release();
+        self.held = Some(Held {
+            key,
+            toggle,
+            seen: now,
+        });
+        if let Some(action) = self.keymap.get(&key) {
+            self.events.push_back(KeyEvent::Pressed(*action));
+        }
+    }
+
+    /// Releases the held key once its frames stop
+    pub fn tick(&mut self, now: Instant) {
+        let expired = self
+            .held
+            .as_ref()
+            .is_some_and(|held| now.saturating_duration_since(held.seen) > self.hold_timeout);
+
+        if expired {
+            self.release();
+        }
+    }
+
+    /// Takes the queued events
+    pub fn events(&mut self) -> impl Iterator<Item = KeyEvent<A>> + '_ {
+        self.events.drain(..)
+    }
+
+    fn release(&mut self) {
+        if let Some(held) = self.held.take() {
+            if let Some(action) = self.keymap.get(&held.key) {
+                self.events.push_back(KeyEvent::Released(*action));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UP: Key = Key::Nec {
+        address: 0x00,
+        command: 0x18,
+    };
+
+    fn nec(key: Key) -> Frame {
+        match key {
+            Key::Nec { address, command } => Frame::Nec {
+                address,
+                command,
+                extended: false,
+            },
+            Key::Rc5 { .. } => unreachable!(),
+        }
+    }
+
+    fn rc5(command: u8, toggle: bool) -> Frame {
+        Frame::Rc5 {
+            address: 0,
+            command,
+            toggle,
+        }
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn nec_hold_and_release() {
+        let mut remote = Remote::new();
+        remote.bind(UP, 'u');
+        let t = Instant::now();
+
+        remote.feed(nec(UP), t);
+        assert_eq!(
+            remote.events().collect::<Vec<_>>(),
+            [KeyEvent::Pressed('u')]
+        );
+
+        // repeat codes keep it held past the timeout
+        for i in 1..5 {
+            remote.feed(Frame::NecRepeat, t + ms(110 * i));
+            remote.tick(t + ms(110 * i));
+        }
+        assert_eq!(remote.held(), Some('u'));
+        assert_eq!(remote.events().count(), 0);
+
+        remote.tick(t + ms(440) + HOLD_TIMEOUT + ms(1));
+        assert_eq!(remote.held(), None);
+        assert_eq!(
+            remote.events().collect::<Vec<_>>(),
+            [KeyEvent::Released('u')]
+        );
+    }
+
+    #[test]
+    fn repeat_without_held_key() {
+        let mut remote: Remote<char> = Remote::new();
+        remote.feed(Frame::NecRepeat, Instant::now());
+
+        assert_eq!(remote.held(), None);
+        assert_eq!(remote.events().count(), 0);
+    }
+
+    #[test]
+    fn rc5_toggle_tells_presses_apart() {
+        let mut remote = Remote::new();
+        remote.bind(
+            Key::Rc5 {
+                address: 0,
+                command: 0x10,
+            },
+            'v',
+        );
+        let t = Instant::now();
+
+        remote.feed(rc5(0x10, false), t);
+        remote.feed(rc5(0x10, false), t + ms(114));
+        assert_eq!(
+            remote.events().collect::<Vec<_>>(),
+            [KeyEvent::Pressed('v')]
+        );
+
+        // pressed again quicker than the hold timeout
+        remote.feed(rc5(0x10, true), t + ms(228));
+        assert_eq!(
+            remote.events().collect::<Vec<_>>(),
+            [KeyEvent::Released('v'), KeyEvent::Pressed('v')]
+        );
+    }
+
+    #[test]
+    fn another_key_releases_the_held_one() {
+        let mut remote = Remote::new();
+        let down = Key::Nec {
+            address: 0x00,
+            command: 0x52,
+        };
+        remote.bind(UP, 'u');
+        remote.bind(down, 'd');
+        let t = Instant::now();
+
+        remote.feed(nec(UP), t);
+        remote.feed(nec(down), t + ms(50));
+        assert_eq!(
+            remote.events().collect::<Vec<_>>(),
+            [
+                KeyEvent::Pressed('u'),
+                KeyEvent::Released('u'),
+                KeyEvent::Pressed('d')
+            ]
+        );
+    }
+
+    #[test]
+    fn unbound_keys_have_no_events() {
+        let mut remote = Remote::new();
+        remote.bind(UP, 'u');
+        let t = Instant::now();
+
+        remote.feed(rc5(0x01, false), t);
+        remote.tick(t + HOLD_TIMEOUT * 2);
+
+        assert_eq!(remote.held(), None);
+        assert_eq!(remote.events().count(), 0);
+    }
+}
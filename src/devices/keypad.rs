@@ -0,0 +1,269 @@
+//! Matrix keypads such as the 4x4 membrane ones
+//!
+//! Rows are driven low one at a time and the columns, pulled up, read low
+//! where a key connects them to the driven row. Idle rows are released
+//! rather than driven high, so two held keys in one column never short a
+//! low row to a high one.
+
+use embedded_hal::digital::v2::{InputPin, OutputPin};
+use rppal::gpio::{IoPin, Mode};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+/// Labels of the common 4x4 keypad
+pub const KEYMAP_4X4: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+/// Labels of the common 3x4 keypad
+pub const KEYMAP_3X4: [[char; 3]; 4] = [
+    ['1', '2', '3'],
+    ['4', '5', '6'],
+    ['7', '8', '9'],
+    ['*', '0', '#'],
+];
+
+/// A row line, either pulling low or left floating
+pub trait RowPin {
+    type Error;
+
+    fn drive_low(&mut self) -> Result<(), Self::Error>;
+
+    /// Stops driving the line
+    fn release(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Switches between a low output and an input
+impl RowPin for IoPin {
+    type Error = Infallible;
+
+    fn drive_low(&mut self) -> Result<(), Infallible> {
+        // the level first, so the line never glitches high
+        self.set_low();
+        self.set_mode(Mode::Output);
+        Ok(())
+    }
+
+    fn release(&mut self) -> Result<(), Infallible> {
+        self.set_mode(Mode::Input);
+        Ok(())
+    }
+}
+
+/// An open-drain output, such as a PCF8574 pin, where high releases the
+/// line
+pub struct OpenDrain<P>(pub P);
+
+impl<P, E> RowPin for OpenDrain<P>
+where
+    P: OutputPin<Error = E>,
+{
+    type Error = E;
+
+    fn drive_low(&mut self) -> Result<(), E> {
+        self.0.set_low()
+    }
+
+    fn release(&mut self) -> Result<(), E> {
+        self.0.set_high()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KeyEvent {
+    Pressed(char),
+    Released(char),
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// How long a key must be stable before a change counts
+    pub debounce: Duration,
+    /// Wait after driving a row before reading the columns
+    pub settle: Duration,
+    /// With a diode per key every combination reads correctly, without
+    /// them some combinations of 3 or more keys are ambiguous
+    pub diodes: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            debounce: Duration::from_millis(20),
+            settle: Duration::from_micros(10),
+            diodes: false,
+        }
+    }
+}
+
+/// A `ROWS` x `COLS` keypad
+///
+/// Rows are [`RowPin`]s, e.g. rppal's `into_io(Mode::Input)`, and columns
+/// need pull-ups, e.g. rppal's `into_input_pullup()`. Call
+/// [`update`](Self::update) every few milliseconds and take the
+/// [`events`](Self::events).
+pub struct Keypad<R, C, E, const ROWS: usize, const COLS: usize>
+where
+    R: RowPin<Error = E>,
+    C: InputPin<Error = E>,
+{
+    rows: [R; ROWS],
+    cols: [C; COLS],
+    keymap: [[char; COLS]; ROWS],
+    config: Config,
+    raw: [[bool; COLS]; ROWS],
+    raw_since: [[Instant; COLS]; ROWS],
+    pressed: [[bool; COLS]; ROWS],
+    ghosting: bool,
+    events: VecDeque<KeyEvent>,
+}
+
+impl<R, C, E, const ROWS: usize, const COLS: usize> Keypad<R, C, E, ROWS, COLS>
+where
+    R: RowPin<Error = E>,
+    C: InputPin<Error = E>,
+{
+    /// Creates a new `Keypad` with every row released
+    pub fn new(
+        mut rows: [R; ROWS],
+        cols: [C; COLS],
+        keymap: [[char; COLS]; ROWS],
+        config: Config,
+    ) -> Result<Self, E> {
+        for row in rows.iter_mut() {
+            row.release()?;
+        }
+
+        Ok(Self {
+            rows,
+            cols,
+            keymap,
+            config,
+            raw: [[false; COLS]; ROWS],
+            raw_since: [[Instant::now(); COLS]; ROWS],
+            pressed: [[false; COLS]; ROWS],
+            ghosting: false,
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Scans the keypad and queues events for debounced changes
+    ///
+    /// While the keys held are ambiguous nothing changes, see
+    /// [`is_ghosting`](Self::is_ghosting).
+    pub fn update(&mut self, now: Instant) -> Result<(), E> {
+        let scan = self.scan()?;
+
+        self.ghosting = !self.config.diodes && is_ambiguous(&scan);
+        if self.ghosting {
+            return Ok(());
+        }
+
+        for (r, row) in scan.iter().enumerate() {
+            for (c, held) in row.iter().enumerate() {
+                self.update_key(r, c, *held, now);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether the last scan had more keys held than the matrix can tell
+    /// apart
+    ///
+    /// Without diodes, three keys on the corners of a rectangle make the
+    /// fourth corner read as pressed too.
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    /// Takes the queued events
+    pub fn events(&mut self) -> impl Iterator<Item = KeyEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Keys currently held, after debouncing
+    pub fn pressed_keys(&self) -> Vec<char> {
+        let mut keys = Vec::new();
+        for (r, row) in self.pressed.iter().enumerate() {
+            for (c, pressed) in row.iter().enumerate() {
+                if *pressed {
+                    keys.push(self.keymap[r][c]);
+                }
+            }
+        }
+        keys
+    }
+
+    pub fn is_pressed(&self, key: char) -> bool {
+        self.pressed_keys().contains(&key)
+    }
+
+    pub fn set_keymap(&mut self, keymap: [[char; COLS]; ROWS]) {
+        self.keymap = keymap;
+    }
+
+    pub fn release(self) -> ([R; ROWS], [C; COLS]) {
+        (self.rows, self.cols)
+    }
+
+    fn update_key(&mut self, r: usize, c: usize, held: bool, now: Instant) {
+        if held != self.raw[r][c] {
+            self.raw[r][c] = held;
+            self.raw_since[r][c] = now;
+        }
+
+        let stable = now.saturating_duration_since(self.raw_since[r][c]) >= self.config.debounce;
+        if stable && held != self.pressed[r][c] {
+            self.pressed[r][c] = held;
+            let key = self.keymap[r][c];
+            self.events.push_back(if held {
+                KeyEvent::Pressed(key)
+            } else {
+                KeyEvent::Released(key)
+            });
+        }
+    }
+
+    fn scan(&mut self) -> Result<[[bool; COLS]; ROWS], E> {
+        let mut scan = [[false; COLS]; ROWS];
+
+        for (r, row) in self.rows.iter_mut().enumerate() {
+            row.drive_low()?;
+            sleep(self.config.settle);
+
+            let result = read_cols(&self.cols, &mut scan[r]);
+            // leave the row released even when reading failed
+            row.release()?;
+            result?;
+        }
+
+        Ok(scan)
+    }
+}
+
+fn read_cols<C, E>(cols: &[C], keys: &mut [bool]) -> Result<(), E>
+where
+    C: InputPin<Error = E>,
+{
+    for (key, col) in keys.iter_mut().zip(cols.iter()) {
+        *key = col.is_low()?;
+    }
+    Ok(())
+}
+
+/// A held key sharing its row with one held key and its column with
+/// another closes a rectangle
+fn is_ambiguous<const ROWS: usize, const COLS: usize>(scan: &[[bool; COLS]; ROWS]) -> bool {
+    let in_row = |r: usize| scan[r].iter().filter(|k| **k).count();
+    let in_col = |c: usize| scan.iter().filter(|row| row[c]).count();
+
+    (0..ROWS).any(|r| (0..COLS).any(|c| scan[r][c] && in_row(r) > 1 && in_col(c) > 1))
+}
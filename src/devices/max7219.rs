@@ -0,0 +1,266 @@
+//! MAX7219 8-digit LED display driver over SPI
+//!
+//! Cascaded chips share CS and CLK, DOUT of one chip goes to DIN of the
+//! next. Chip 0 is the one wired to the Pi. rppal's `Spi` toggles CS once
+//! per write, which latches every chip at the same time.
+
+use super::seven_segment;
+use embedded_hal::blocking::spi::Write;
+
+pub mod font;
+pub mod matrix;
+
+const NOOP: u8 = 0x00;
+const DIGIT0: u8 = 0x01;
+const DECODE_MODE: u8 = 0x09;
+const INTENSITY: u8 = 0x0a;
+const SCAN_LIMIT: u8 = 0x0b;
+const SHUTDOWN: u8 = 0x0c;
+const DISPLAY_TEST: u8 = 0x0f;
+
+/// Maximum brightness for [`MAX7219::set_intensity`]
+pub const MAX_INTENSITY: u8 = 0x0f;
+
+/// Decimal point in both Code B and raw segment data
+pub const DP: u8 = 1 << 7;
+
+/// Code B characters, used when a digit has decoding enabled
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CodeB {
+    /// 0 to 9
+    Digit(u8),
+    Minus,
+    E,
+    H,
+    L,
+    P,
+    Blank,
+}
+
+impl CodeB {
+    pub fn bits(self) -> u8 {
+        match self {
+            CodeB::Digit(d) => d.min(9),
+            CodeB::Minus => 0x0a,
+            CodeB::E => 0x0b,
+            CodeB::H => 0x0c,
+            CodeB::L => 0x0d,
+            CodeB::P => 0x0e,
+            CodeB::Blank => 0x0f,
+        }
+    }
+}
+
+/// Converts a [`seven_segment`] pattern (`0bPGFEDCBA`) to the MAX7219's
+/// raw segment order (`0bPABCDEFG`)
+pub fn segments(pattern: u8) -> u8 {
+    let mut raw = pattern & DP;
+    for bit in 0..7 {
+        if pattern & (1 << bit) != 0 {
+            raw |= 1 << (6 - bit);
+        }
+    }
+    raw
+}
+
+/// A chain of `MAX7219`s
+pub struct MAX7219<SPI> {
+    spi: SPI,
+    devices: usize,
+    buf: Vec<u8>,
+}
+
+impl<SPI, E> MAX7219<SPI>
+where
+    SPI: Write<u8, Error = E>,
+{
+    /// Creates a new `MAX7219` chain of `devices` chips
+    ///
+    /// Every chip is set up to scan all 8 digits with decoding off, cleared
+    /// and taken out of shutdown.
+    pub fn new(spi: SPI, devices: usize) -> Result<Self, E> {
+        let mut max = Self {
+            spi,
+            devices,
+            buf: vec![0; devices * 2],
+        };
+
+        max.set_test_mode(false)?;
+        max.write_all(SCAN_LIMIT, 7)?;
+        max.write_all(DECODE_MODE, 0)?;
+        max.write_all(INTENSITY, MAX_INTENSITY / 2)?;
+        max.clear_all()?;
+        max.wake_all()?;
+
+        Ok(max)
+    }
+
+    /// Number of chips in the chain
+    pub fn devices(&self) -> usize {
+        self.devices
+    }
+
+    /// Sets the brightness of `device`, `0..=MAX_INTENSITY`
+    pub fn set_intensity(&mut self, device: usize, intensity: u8) -> Result<(), E> {
+        self.write(device, INTENSITY, intensity.min(MAX_INTENSITY))
+    }
+
+    pub fn set_intensity_all(&mut self, intensity: u8) -> Result<(), E> {
+        self.write_all(INTENSITY, intensity.min(MAX_INTENSITY))
+    }
+
+    /// Limits scanning to the first `digits` digits (1..=8)
+    ///
+    /// Fewer digits are brighter, but the segment current must be sized for
+    /// it, see the datasheet.
+    pub fn set_scan_limit(&mut self, device: usize, digits: u8) -> Result<(), E> {
+        self.write(device, SCAN_LIMIT, digits.clamp(1, 8) - 1)
+    }
+
+    /// Turns the display of `device` off, register contents are kept
+    pub fn shutdown(&mut self, device: usize) -> Result<(), E> {
+        self.write(device, SHUTDOWN, 0)
+    }
+
+    pub fn wake(&mut self, device: usize) -> Result<(), E> {
+        self.write(device, SHUTDOWN, 1)
+    }
+
+    pub fn shutdown_all(&mut self) -> Result<(), E> {
+        self.write_all(SHUTDOWN, 0)
+    }
+
+    pub fn wake_all(&mut self) -> Result<(), E> {
+        self.write_all(SHUTDOWN, 1)
+    }
+
+    /// Lights every LED of every chip at full brightness while enabled
+    pub fn set_test_mode(&mut self, enabled: bool) -> Result<(), E> {
+        self.write_all(DISPLAY_TEST, enabled as u8)
+    }
+
+    /// Enables Code B decoding for the digits set in `mask`, bit 0 is digit 0
+    pub fn set_decode_mode(&mut self, device: usize, mask: u8) -> Result<(), E> {
+        self.write(device, DECODE_MODE, mask)
+    }
+
+    /// Writes a digit register
+    ///
+    /// `data` is Code B or raw segments (`0bPABCDEFG`) depending on the
+    /// decode mode. On LED matrices each digit is one row.
+    pub fn write_digit(&mut self, device: usize, digit: u8, data: u8) -> Result<(), E> {
+        assert!(digit < 8, "digit out of range");
+
+        self.write(device, DIGIT0 + digit, data)
+    }
+
+    /// Writes a Code B character, decoding must be on for `digit`
+    pub fn write_code_b(
+        &mut self,
+        device: usize,
+        digit: u8,
+        value: CodeB,
+        dp: bool,
+    ) -> Result<(), E> {
+        let dp = if dp { DP } else { 0 };
+        self.write_digit(device, digit, value.bits() | dp)
+    }
+
+    /// Writes register `digit` of every chip in one go, `rows[0]` goes to
+    /// chip 0
+    pub fn write_row(&mut self, digit: u8, rows: &[u8]) -> Result<(), E> {
+        assert!(digit < 8, "digit out of range");
+
+        self.buf.fill(NOOP);
+        for (device, data) in rows.iter().enumerate().take(self.devices) {
+            let i = self.offset(device);
+            self.buf[i] = DIGIT0 + digit;
+            self.buf[i + 1] = *data;
+        }
+        self.flush()
+    }
+
+    pub fn clear(&mut self, device: usize) -> Result<(), E> {
+        for digit in 0..8 {
+            self.write_digit(device, digit, 0)?;
+        }
+        Ok(())
+    }
+
+    pub fn clear_all(&mut self) -> Result<(), E> {
+        for digit in 0..8 {
+            self.write_all(DIGIT0 + digit, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Shows `value` right aligned on an 8-digit module using Code B
+    ///
+    /// Digit 0 is the rightmost. Shows dashes when the number doesn't fit.
+    pub fn show_number(&mut self, device: usize, value: i64) -> Result<(), E> {
+        let text = value.to_string();
+        let mut digits = [CodeB::Blank; 8];
+
+        if text.len() > 8 {
+            digits = [CodeB::Minus; 8];
+        } else {
+            for (digit, c) in digits.iter_mut().zip(text.bytes().rev()) {
+                *digit = match c {
+                    b'-' => CodeB::Minus,
+                    c => CodeB::Digit(c - b'0'),
+                };
+            }
+        }
+
+        self.set_decode_mode(device, 0xff)?;
+        for (i, digit) in digits.iter().enumerate() {
+            self.write_code_b(device, i as u8, *digit, false)?;
+        }
+        Ok(())
+    }
+
+    /// Shows text left aligned on an 8-digit module with the
+    /// [`seven_segment`] font, cut to fit
+    pub fn show_str(&mut self, device: usize, text: &str) -> Result<(), E> {
+        let patterns = seven_segment::text_segments(text);
+
+        self.set_decode_mode(device, 0)?;
+        for digit in 0..8 {
+            let pattern = patterns.get(digit).copied().unwrap_or(0);
+            self.write_digit(device, 7 - digit as u8, segments(pattern))?;
+        }
+        Ok(())
+    }
+
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+
+    /// Writes one register of `device`, other chips get a no-op
+    fn write(&mut self, device: usize, register: u8, data: u8) -> Result<(), E> {
+        assert!(device < self.devices, "device out of range");
+
+        self.buf.fill(NOOP);
+        let i = self.offset(device);
+        self.buf[i] = register;
+        self.buf[i + 1] = data;
+        self.flush()
+    }
+
+    fn write_all(&mut self, register: u8, data: u8) -> Result<(), E> {
+        for pair in self.buf.chunks_mut(2) {
+            pair[0] = register;
+            pair[1] = data;
+        }
+        self.flush()
+    }
+
+    /// The first pair shifted out ends up in the last chip
+    fn offset(&self, device: usize) -> usize {
+        (self.devices - 1 - device) * 2
+    }
+
+    fn flush(&mut self) -> Result<(), E> {
+        self.spi.write(&self.buf)
+    }
+}
@@ -0,0 +1,156 @@
+//! Cascaded 8x8 LED matrices with a frame buffer
+
+use super::{font, MAX7219};
+use embedded_hal::blocking::spi::Write;
+use std::time::{Duration, Instant};
+
+/// Blank columns between characters
+const SPACING: usize = 1;
+
+struct Scroll {
+    columns: Vec<u8>,
+    step: Duration,
+    started: Instant,
+}
+
+/// A row of 8x8 matrices, one per chip, chip 0 on the left
+///
+/// Digit register `r` drives row `r` and bit 7 the leftmost column, as on
+/// the common FC-16 modules. Draw into the buffer, then
+/// [`show`](Self::show) it.
+pub struct Matrix<SPI> {
+    max: MAX7219<SPI>,
+    /// One byte per column, bit 0 is the top row
+    columns: Vec<u8>,
+    scroll: Option<Scroll>,
+}
+
+impl<SPI, E> Matrix<SPI>
+where
+    SPI: Write<u8, Error = E>,
+{
+    pub fn new(max: MAX7219<SPI>) -> Self {
+        let width = max.devices() * 8;
+
+        Self {
+            max,
+            columns: vec![0; width],
+            scroll: None,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub fn height(&self) -> usize {
+        8
+    }
+
+    /// Out of range pixels are ignored
+    pub fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if let (Some(column), true) = (self.columns.get_mut(x), y < 8) {
+            if on {
+                *column |= 1 << y;
+            } else {
+                *column &= !(1 << y);
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> bool {
+        y < 8 && self.columns.get(x).is_some_and(|c| c & (1 << y) != 0)
+    }
+
+    pub fn clear(&mut self) {
+        self.scroll = None;
+        self.columns.fill(0);
+    }
+
+    /// Draws text with its left edge at `x` and returns the width drawn
+    ///
+    /// Stops any scrolling text.
+    pub fn draw_text(&mut self, x: i32, text: &str) -> usize {
+        self.scroll = None;
+
+        let columns = text_columns(text);
+        for (i, column) in columns.iter().enumerate() {
+            let col = x + i as i32;
+            if col >= 0 {
+                if let Some(c) = self.columns.get_mut(col as usize) {
+                    *c = *column;
+                }
+            }
+        }
+
+        columns.len()
+    }
+
+    /// Scrolls `text` right to left, one column every `step`
+    pub fn scroll_text(&mut self, text: &str, step: Duration, now: Instant) {
+        let mut columns = vec![0; self.width()];
+        columns.extend(text_columns(text));
+
+        self.scroll = Some(Scroll {
+            columns,
+            step,
+            started: now,
+        });
+        self.tick(now);
+    }
+
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll.is_some()
+    }
+
+    /// Advances scrolling text, call [`show`](Self::show) afterward
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(scroll) = &self.scroll {
+            let steps = now.saturating_duration_since(scroll.started).as_secs_f64()
+                / scroll.step.as_secs_f64();
+            let offset = steps as usize % scroll.columns.len();
+
+            for (i, column) in self.columns.iter_mut().enumerate() {
+                *column = scroll.columns.get(offset + i).copied().unwrap_or(0);
+            }
+        }
+    }
+
+    /// Sends the frame buffer to the matrices
+    pub fn show(&mut self) -> Result<(), E> {
+        let mut rows = vec![0; self.max.devices()];
+
+        for y in 0..8 {
+            for (row, columns) in rows.iter_mut().zip(self.columns.chunks(8)) {
+                *row = columns
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, c)| *c & (1 << y) != 0)
+                    .fold(0, |row, (x, _)| row | (0x80 >> x));
+            }
+            self.max.write_row(y as u8, &rows)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn max7219_mut(&mut self) -> &mut MAX7219<SPI> {
+        &mut self.max
+    }
+
+    pub fn release(self) -> MAX7219<SPI> {
+        self.max
+    }
+}
+
+/// Renders text into columns with the built-in font
+pub fn text_columns(text: &str) -> Vec<u8> {
+    let mut columns = Vec::with_capacity(text.len() * (font::WIDTH + SPACING));
+
+    for c in text.chars() {
+        columns.extend_from_slice(font::glyph(c));
+        columns.resize(columns.len() + SPACING, 0);
+    }
+
+    columns
+}
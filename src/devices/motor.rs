@@ -2,9 +2,25 @@ use core::marker::PhantomData;
 
 use embedded_hal::digital::v2::OutputPin;
 use embedded_hal::PwmPin;
+use switch_hal::OutputSwitch;
+
+use crate::utils::Duty;
 
 pub mod ic;
 
+/// Anything that turns a wheel at a signed speed
+///
+/// `speed` is `-1.0..=1.0`, positive is `ClockWise` and `0.0` stops.
+pub trait Drive {
+    type Error;
+
+    fn set_speed(&mut self, speed: f64) -> Result<(), Self::Error>;
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.set_speed(0.0)
+    }
+}
+
 pub struct Motor<IN1, IN2, PWM, E, IC>
 where
     IN1: OutputPin<Error = E>,
@@ -55,6 +71,31 @@ where
     }
 }
 
+impl<IN1, IN2, PWM, E, IC> Drive for Motor<IN1, IN2, PWM, E, IC>
+where
+    IN1: OutputPin<Error = E>,
+    IN2: OutputPin<Error = E>,
+    PWM: PwmPin,
+    PWM::Duty: Duty,
+{
+    type Error = E;
+
+    /// Coasts at `0.0`
+    fn set_speed(&mut self, speed: f64) -> Result<(), E> {
+        let speed = speed.clamp(-1.0, 1.0);
+        let cmd = if speed > 0.0 {
+            Command::ClockWise
+        } else if speed < 0.0 {
+            Command::CounterClockWise
+        } else {
+            Command::Coast
+        };
+        let duty = Duty::from_ratio(self.pwm.get_max_duty(), speed.abs());
+
+        self.run(cmd, duty)
+    }
+}
+
 impl<IN1, IN2, PWM, E> Motor<IN1, IN2, PWM, E, ic::L298>
 where
     IN1: OutputPin<Error = E>,
@@ -100,3 +141,33 @@ where
         })
     }
 }
+
+/// The STBY pin of a TB6612FNG, shared by both of its motors
+///
+/// The driver runs while STBY is high, so pass
+/// `pin.into_active_high_switch()`, or an active low switch behind an
+/// inverter.
+pub struct Standby<S> {
+    switch: S,
+}
+
+impl<S> Standby<S>
+where
+    S: OutputSwitch,
+{
+    /// Creates a new `Standby`, driver running
+    pub fn new(mut switch: S) -> Result<Self, S::Error> {
+        switch.on()?;
+        Ok(Self { switch })
+    }
+
+    /// Runs the driver
+    pub fn wake(&mut self) -> Result<(), S::Error> {
+        self.switch.on()
+    }
+
+    /// Puts the driver in standby, both motors stop
+    pub fn standby(&mut self) -> Result<(), S::Error> {
+        self.switch.off()
+    }
+}
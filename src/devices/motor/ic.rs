@@ -10,7 +10,8 @@
 ///
 /// where x = A or B
 ///
-/// **NOTE** The STANDBY (STBY) pin needs to be driven high
+/// **NOTE** The STANDBY (STBY) pin needs to be driven high, e.g. by a
+/// [`Standby`](super::Standby) shared by both motors
 pub struct TB6612FNG;
 
 /// L298, dual full-bridge driver
@@ -0,0 +1,406 @@
+//! MPU6050, 3-axis accelerometer and gyroscope over I2C
+//!
+//! Samples can be read one at a time, when INT signals data ready, or in
+//! batches from the 1 KB FIFO. [`filter`] turns them into roll, pitch and
+//! yaw on a thread of its own.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use std::fmt;
+use std::{thread::sleep, time::Duration};
+
+pub mod filter;
+
+pub use filter::{ComplementaryFilter, Orientation, Tracker};
+
+/// AD0 low, AD0 high is 0x69
+pub const DEFAULT_ADDR: u8 = 0x68;
+pub const ALT_ADDR: u8 = 0x69;
+
+const SMPLRT_DIV: u8 = 0x19;
+const CONFIG: u8 = 0x1a;
+const GYRO_CONFIG: u8 = 0x1b;
+const ACCEL_CONFIG: u8 = 0x1c;
+const FIFO_EN: u8 = 0x23;
+const INT_PIN_CFG: u8 = 0x37;
+const INT_ENABLE: u8 = 0x38;
+const INT_STATUS: u8 = 0x3a;
+const ACCEL_XOUT_H: u8 = 0x3b;
+const TEMP_OUT_H: u8 = 0x41;
+const USER_CTRL: u8 = 0x6a;
+const PWR_MGMT_1: u8 = 0x6b;
+const FIFO_COUNT_H: u8 = 0x72;
+const FIFO_R_W: u8 = 0x74;
+const WHO_AM_I: u8 = 0x75;
+
+const FIFO_EN_GYRO: u8 = 0b0111_0000;
+const FIFO_EN_ACCEL: u8 = 1 << 3;
+const INT_LEVEL: u8 = 1 << 7;
+const INT_RD_CLEAR: u8 = 1 << 4;
+const DATA_RDY: u8 = 1 << 0;
+const USER_CTRL_FIFO_EN: u8 = 1 << 6;
+const USER_CTRL_FIFO_RESET: u8 = 1 << 2;
+const PWR_MGMT_1_RESET: u8 = 1 << 7;
+/// PLL with the X gyro as reference, steadier than the internal oscillator
+const PWR_MGMT_1_CLK_PLL: u8 = 0x01;
+
+const FIFO_SIZE: u16 = 1024;
+/// Accelerometer then gyroscope, 3 big-endian words each
+const FIFO_SAMPLE: usize = 12;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum AccelRange {
+    G2 = 0,
+    G4 = 1,
+    G8 = 2,
+    G16 = 3,
+}
+
+impl AccelRange {
+    /// LSB per g
+    pub fn sensitivity(self) -> f64 {
+        16384.0 / f64::from(1_u16 << self as u8)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GyroRange {
+    Deg250 = 0,
+    Deg500 = 1,
+    Deg1000 = 2,
+    Deg2000 = 3,
+}
+
+impl GyroRange {
+    /// LSB per degree per second
+    pub fn sensitivity(self) -> f64 {
+        131.0 / f64::from(1_u16 << self as u8)
+    }
+}
+
+/// Bandwidth of the digital low pass filter, for both sensors
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Dlpf {
+    /// Off, the gyro then samples at 8 kHz
+    Hz260 = 0,
+    Hz184 = 1,
+    Hz94 = 2,
+    Hz44 = 3,
+    Hz21 = 4,
+    Hz10 = 5,
+    Hz5 = 6,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Vector {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Vector {
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn magnitude(&self) -> f64 {
+        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Sample {
+    /// In g
+    pub accel: Vector,
+    /// In degrees per second, without the gyro bias
+    pub gyro: Vector,
+}
+
+#[derive(Debug)]
+pub enum Error<E> {
+    I2c(E),
+    /// WHO_AM_I answered something else
+    WrongDevice(u8),
+    /// The FIFO filled up and was reset, samples were lost
+    FifoOverflow,
+    /// The sensor moved while calibrating
+    Moved,
+    /// The [`Tracker`](filter::Tracker) thread panicked
+    Panicked,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::I2c(e)
+    }
+}
+
+impl<E: fmt::Debug> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::I2c(e) => write!(f, "i2c error: {:?}", e),
+            Error::WrongDevice(id) => write!(f, "not an MPU6050, WHO_AM_I is {:#04x}", id),
+            Error::FifoOverflow => write!(f, "fifo overflow"),
+            Error::Moved => write!(f, "moved while calibrating"),
+            Error::Panicked => write!(f, "tracker thread panicked"),
+        }
+    }
+}
+
+impl<E: fmt::Debug> std::error::Error for Error<E> {}
+
+pub struct MPU6050<I2C> {
+    i2c: I2C,
+    addr: u8,
+    accel_range: AccelRange,
+    gyro_range: GyroRange,
+    dlpf: Dlpf,
+    sample_rate: f64,
+    gyro_bias: Vector,
+}
+
+impl<I2C, E> MPU6050<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Resets and wakes the sensor
+    ///
+    /// It starts at ±2 g, ±250 °/s, 44 Hz bandwidth and 100 samples per
+    /// second.
+    pub fn new(i2c: I2C, addr: u8) -> Result<Self, Error<E>> {
+        let mut imu = Self {
+            i2c,
+            addr,
+            accel_range: AccelRange::G2,
+            gyro_range: GyroRange::Deg250,
+            dlpf: Dlpf::Hz44,
+            sample_rate: 1000.0,
+            gyro_bias: Vector::default(),
+        };
+
+        // clones such as the MPU6500 answer 0x70, some GY-521 boards 0x98
+        let id = imu.read_reg(WHO_AM_I)?;
+        if !matches!(id, 0x68 | 0x70 | 0x98) {
+            return Err(Error::WrongDevice(id));
+        }
+
+        imu.write_reg(PWR_MGMT_1, PWR_MGMT_1_RESET)?;
+        sleep(Duration::from_millis(100));
+        imu.write_reg(PWR_MGMT_1, PWR_MGMT_1_CLK_PLL)?;
+
+        imu.set_accel_range(AccelRange::G2)?;
+        imu.set_gyro_range(GyroRange::Deg250)?;
+        imu.set_dlpf(Dlpf::Hz44)?;
+        imu.set_sample_rate(100.0)?;
+
+        Ok(imu)
+    }
+
+    pub fn set_accel_range(&mut self, range: AccelRange) -> Result<(), E> {
+        self.write_reg(ACCEL_CONFIG, (range as u8) << 3)?;
+        self.accel_range = range;
+        Ok(())
+    }
+
+    pub fn accel_range(&self) -> AccelRange {
+        self.accel_range
+    }
+
+    pub fn set_gyro_range(&mut self, range: GyroRange) -> Result<(), E> {
+        self.write_reg(GYRO_CONFIG, (range as u8) << 3)?;
+        self.gyro_range = range;
+        Ok(())
+    }
+
+    pub fn gyro_range(&self) -> GyroRange {
+        self.gyro_range
+    }
+
+    /// Sets the low pass filter, call [`set_sample_rate`](Self::set_sample_rate)
+    /// again afterwards as the gyro's output rate depends on it
+    pub fn set_dlpf(&mut self, dlpf: Dlpf) -> Result<(), E> {
+        self.write_reg(CONFIG, dlpf as u8)?;
+        self.dlpf = dlpf;
+        Ok(())
+    }
+
+    /// Sets how often samples are taken (4 Hz up to 1 kHz, 8 kHz without
+    /// the low pass filter)
+    ///
+    /// Returns the actual rate after rounding the divider.
+    pub fn set_sample_rate(&mut self, rate: f64) -> Result<f64, E> {
+        let output_rate = if self.dlpf == Dlpf::Hz260 {
+            8000.0
+        } else {
+            1000.0
+        };
+        let divider = (output_rate / rate).round().clamp(1.0, 256.0);
+
+        self.write_reg(SMPLRT_DIV, (divider - 1.0) as u8)?;
+        self.sample_rate = output_rate / divider;
+
+        Ok(self.sample_rate)
+    }
+
+    pub fn sample_rate(&self) -> f64 {
+        self.sample_rate
+    }
+
+    /// Reads the latest sample
+    pub fn read(&mut self) -> Result<Sample, E> {
+        let mut buf = [0; 14];
+        self.i2c.write_read(self.addr, &[ACCEL_XOUT_H], &mut buf)?;
+
+        // the temperature sits between the two
+        let mut raw = [0; 12];
+        raw[..6].copy_from_slice(&buf[..6]);
+        raw[6..].copy_from_slice(&buf[8..]);
+
+        Ok(self.sample(&raw))
+    }
+
+    /// Die temperature in °C
+    pub fn temperature(&mut self) -> Result<f64, E> {
+        let mut buf = [0; 2];
+        self.i2c.write_read(self.addr, &[TEMP_OUT_H], &mut buf)?;
+
+        Ok(f64::from(i16::from_be_bytes(buf)) / 340.0 + 36.53)
+    }
+
+    /// Pulses INT for 50 us whenever a new sample is ready
+    ///
+    /// INT is active high unless `active_low`. Reading
+    /// [`is_data_ready`](Self::is_data_ready) clears the flag.
+    pub fn enable_data_ready(&mut self, active_low: bool) -> Result<(), E> {
+        let level = if active_low { INT_LEVEL } else { 0 };
+        self.write_reg(INT_PIN_CFG, level | INT_RD_CLEAR)?;
+        self.write_reg(INT_ENABLE, DATA_RDY)
+    }
+
+    pub fn disable_data_ready(&mut self) -> Result<(), E> {
+        self.write_reg(INT_ENABLE, 0)
+    }
+
+    /// Whether a new sample arrived since the last call
+    pub fn is_data_ready(&mut self) -> Result<bool, E> {
+        Ok(self.read_reg(INT_STATUS)? & DATA_RDY != 0)
+    }
+
+    /// Empties the FIFO and starts queueing accelerometer and gyro samples
+    pub fn enable_fifo(&mut self) -> Result<(), E> {
+        self.write_reg(FIFO_EN, FIFO_EN_GYRO | FIFO_EN_ACCEL)?;
+        self.reset_fifo()
+    }
+
+    pub fn disable_fifo(&mut self) -> Result<(), E> {
+        self.write_reg(USER_CTRL, 0)?;
+        self.write_reg(FIFO_EN, 0)
+    }
+
+    pub fn reset_fifo(&mut self) -> Result<(), E> {
+        self.write_reg(USER_CTRL, USER_CTRL_FIFO_RESET)?;
+        self.write_reg(USER_CTRL, USER_CTRL_FIFO_EN)
+    }
+
+    /// Bytes waiting in the FIFO
+    pub fn fifo_count(&mut self) -> Result<u16, E> {
+        let mut buf = [0; 2];
+        self.i2c.write_read(self.addr, &[FIFO_COUNT_H], &mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    /// Appends the samples queued in the FIFO to `samples`, returns how many
+    ///
+    /// At 100 Hz the FIFO holds 0.85 s of samples. If it was allowed to fill
+    /// up it's reset and [`Error::FifoOverflow`] returned.
+    pub fn read_fifo(&mut self, samples: &mut Vec<Sample>) -> Result<usize, Error<E>> {
+        let count = self.fifo_count()?;
+        if count >= FIFO_SIZE {
+            self.reset_fifo()?;
+            return Err(Error::FifoOverflow);
+        }
+
+        let n = usize::from(count) / FIFO_SAMPLE;
+        let mut buf = vec![0; n * FIFO_SAMPLE];
+        if n > 0 {
+            self.i2c.write_read(self.addr, &[FIFO_R_W], &mut buf)?;
+        }
+
+        samples.extend(buf.chunks(FIFO_SAMPLE).map(|raw| self.sample(raw)));
+        Ok(n)
+    }
+
+    /// Measures the gyro bias by averaging `samples` readings taken
+    /// `interval` apart, the sensor must be kept still
+    ///
+    /// The bias is then taken out of every sample.
+    pub fn calibrate_gyro(
+        &mut self,
+        samples: usize,
+        interval: Duration,
+    ) -> Result<Vector, Error<E>> {
+        // anything wider than this is movement, not noise
+        const MAX_SPREAD: f64 = 5.0;
+
+        self.gyro_bias = Vector::default();
+        let mut sum = Vector::default();
+        let mut min = Vector::new(f64::MAX, f64::MAX, f64::MAX);
+        let mut max = Vector::new(f64::MIN, f64::MIN, f64::MIN);
+
+        for _ in 0..samples.max(1) {
+            let gyro = self.read()?.gyro;
+            sum = Vector::new(sum.x + gyro.x, sum.y + gyro.y, sum.z + gyro.z);
+            min = Vector::new(min.x.min(gyro.x), min.y.min(gyro.y), min.z.min(gyro.z));
+            max = Vector::new(max.x.max(gyro.x), max.y.max(gyro.y), max.z.max(gyro.z));
+            sleep(interval);
+        }
+
+        let spread = Vector::new(max.x - min.x, max.y - min.y, max.z - min.z);
+        if spread.x.max(spread.y).max(spread.z) > MAX_SPREAD {
+            return Err(Error::Moved);
+        }
+
+        let n = samples.max(1) as f64;
+        self.gyro_bias = Vector::new(sum.x / n, sum.y / n, sum.z / n);
+        Ok(self.gyro_bias)
+    }
+
+    /// Sets a bias from an earlier calibration, in degrees per second
+    pub fn set_gyro_bias(&mut self, bias: Vector) {
+        self.gyro_bias = bias;
+    }
+
+    pub fn gyro_bias(&self) -> Vector {
+        self.gyro_bias
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Converts accelerometer and gyro words, as the FIFO stores them
+    fn sample(&self, raw: &[u8]) -> Sample {
+        let word = |i: usize| f64::from(i16::from_be_bytes([raw[2 * i], raw[2 * i + 1]]));
+        let accel = self.accel_range.sensitivity();
+        let gyro = self.gyro_range.sensitivity();
+        let bias = self.gyro_bias;
+
+        Sample {
+            accel: Vector::new(word(0) / accel, word(1) / accel, word(2) / accel),
+            gyro: Vector::new(
+                word(3) / gyro - bias.x,
+                word(4) / gyro - bias.y,
+                word(5) / gyro - bias.z,
+            ),
+        }
+    }
+
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<(), E> {
+        self.i2c.write(self.addr, &[reg, value])
+    }
+
+    fn read_reg(&mut self, reg: u8) -> Result<u8, E> {
+        let mut buf = [0];
+        self.i2c.write_read(self.addr, &[reg], &mut buf)?;
+        Ok(buf[0])
+    }
+}
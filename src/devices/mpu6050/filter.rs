@@ -0,0 +1,254 @@
+//! Roll, pitch and yaw from accelerometer and gyro samples
+//!
+//! The gyro is accurate over short periods but drifts, the accelerometer
+//! knows where down is but is noisy and fooled by acceleration. A
+//! complementary filter trusts the gyro for fast changes and slowly pulls
+//! roll and pitch towards the accelerometer. Nothing corrects yaw, it
+//! drifts with whatever gyro bias is left after calibration.
+
+use super::{Error, Sample, MPU6050};
+use crate::utils::lock;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, sleep, JoinHandle};
+use std::time::Duration;
+
+/// In degrees
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Orientation {
+    /// Around the X axis, -180 to 180
+    pub roll: f64,
+    /// Around the Y axis, -90 to 90
+    pub pitch: f64,
+    /// Around the Z axis, -180 to 180, relative to where it started
+    pub yaw: f64,
+}
+
+impl Orientation {
+    /// Whether the sensor is on its back, mounted Z up
+    pub fn is_upside_down(&self) -> bool {
+        self.roll.abs() > 90.0
+    }
+}
+
+pub struct ComplementaryFilter {
+    /// How much the gyro is trusted, 0.98 is typical
+    alpha: f64,
+    orientation: Orientation,
+    initialized: bool,
+}
+
+impl ComplementaryFilter {
+    pub fn new(alpha: f64) -> Self {
+        Self {
+            alpha: alpha.clamp(0.0, 1.0),
+            orientation: Orientation::default(),
+            initialized: false,
+        }
+    }
+
+    /// Adds a sample taken `dt` seconds after the previous one
+    pub fn update(&mut self, sample: &Sample, dt: f64) -> Orientation {
+        let a = sample.accel;
+        let g = sample.gyro;
+        let roll = a.y.atan2(a.z).to_degrees();
+        let pitch = (-a.x).atan2((a.y * a.y + a.z * a.z).sqrt()).to_degrees();
+
+        let o = &mut self.orientation;
+        if !self.initialized {
+            // the first sample sets roll and pitch straight away
+            o.roll = roll;
+            o.pitch = pitch;
+            self.initialized = true;
+        } else {
+            o.roll = blend(o.roll + g.x * dt, roll, self.alpha);
+            o.pitch = blend(o.pitch + g.y * dt, pitch, self.alpha);
+        }
+        o.yaw = wrap(o.yaw + g.z * dt);
+
+        *o
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    /// Makes the current heading yaw 0
+    pub fn reset_yaw(&mut self) {
+        self.orientation.yaw = 0.0;
+    }
+}
+
+/// Mixes the gyro and accelerometer angles, going the short way around
+/// the ±180 boundary
+fn blend(gyro: f64, accel: f64, alpha: f64) -> f64 {
+    let gyro = wrap(gyro);
+    wrap(gyro + (1.0 - alpha) * wrap(accel - gyro))
+}
+
+fn wrap(angle: f64) -> f64 {
+    let angle = angle % 360.0;
+    if angle > 180.0 {
+        angle - 360.0
+    } else if angle <= -180.0 {
+        angle + 360.0
+    } else {
+        angle
+    }
+}
+
+struct State {
+    filter: ComplementaryFilter,
+    sample: Sample,
+}
+
+/// Runs a [`ComplementaryFilter`] over the sensor's FIFO on a thread
+pub struct Tracker<I2C, E> {
+    state: Arc<Mutex<State>>,
+    running: Arc<AtomicBool>,
+    handle: JoinHandle<Result<MPU6050<I2C>, Error<E>>>,
+}
+
+impl<I2C, E> Tracker<I2C, E>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E> + Send + 'static,
+    E: Send + 'static,
+{
+    /// Starts the FIFO and reads it every `interval`, which must be well
+    /// below the time it takes to fill up
+    ///
+    /// Calibrate the gyro first.
+    pub fn spawn(mut imu: MPU6050<I2C>, alpha: f64, interval: Duration) -> Result<Self, Error<E>> {
+        let state = Arc::new(Mutex::new(State {
+            filter: ComplementaryFilter::new(alpha),
+            sample: Sample::default(),
+        }));
+        let running = Arc::new(AtomicBool::new(true));
+
+        imu.enable_fifo()?;
+
+        let s = state.clone();
+        let r = running.clone();
+        let handle = thread::spawn(move || {
+            let dt = 1.0 / imu.sample_rate();
+            let mut samples = Vec::new();
+
+            while r.load(Ordering::SeqCst) {
+                samples.clear();
+                match imu.read_fifo(&mut samples) {
+                    // a gap only costs some yaw accuracy
+                    Ok(_) | Err(Error::FifoOverflow) => {}
+                    Err(e) => return Err(e),
+                }
+
+                if let Some(last) = samples.last() {
+                    let mut state = lock(&s);
+                    for sample in &samples {
+                        state.filter.update(sample, dt);
+                    }
+                    state.sample = *last;
+                }
+
+                sleep(interval);
+            }
+
+            imu.disable_fifo()?;
+            Ok(imu)
+        });
+
+        Ok(Self {
+            state,
+            running,
+            handle,
+        })
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        lock(&self.state).filter.orientation()
+    }
+
+    /// The latest sample, for acceleration peaks such as crashes
+    pub fn sample(&self) -> Sample {
+        lock(&self.state).sample
+    }
+
+    pub fn reset_yaw(&self) {
+        lock(&self.state).filter.reset_yaw();
+    }
+
+    /// Whether the thread stopped on an I2C error or a panic
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Stops the thread and gives the sensor back, or the error that
+    /// stopped it
+    ///
+    /// The sensor is lost if the thread panicked.
+    pub fn stop(self) -> Result<MPU6050<I2C>, Error<E>> {
+        self.running.store(false, Ordering::SeqCst);
+        self.handle.join().map_err(|_| Error::Panicked)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::mpu6050::Vector;
+
+    fn sample(accel: (f64, f64, f64), gyro: (f64, f64, f64)) -> Sample {
+        Sample {
+            accel: Vector {
+                x: accel.0,
+                y: accel.1,
+                z: accel.2,
+            },
+            gyro: Vector {
+                x: gyro.0,
+                y: gyro.1,
+                z: gyro.2,
+            },
+        }
+    }
+
+    #[test]
+    fn converges_to_accel_when_stationary() {
+        let mut filter = ComplementaryFilter::new(0.98);
+        filter.update(&sample((0.0, 0.0, 1.0), (0.0, 0.0, 0.0)), 0.01);
+
+        // tilted 30 degrees around X, the gyro saw nothing
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        let tilted = sample((0.0, sin, cos), (0.0, 0.0, 0.0));
+        let first = filter.update(&tilted, 0.01);
+        assert!(first.roll > 0.0 && first.roll < 1.0);
+
+        for _ in 0..500 {
+            filter.update(&tilted, 0.01);
+        }
+        let o = filter.orientation();
+        assert!((o.roll - 30.0).abs() < 0.01, "roll {}", o.roll);
+        assert!(o.pitch.abs() < 1e-9);
+        assert_eq!(o.yaw, 0.0);
+    }
+
+    #[test]
+    fn integrates_yaw() {
+        let mut filter = ComplementaryFilter::new(0.98);
+        let turning = sample((0.0, 0.0, 1.0), (0.0, 0.0, 90.0));
+
+        for _ in 0..100 {
+            filter.update(&turning, 0.01);
+        }
+        assert!((filter.orientation().yaw - 90.0).abs() < 1e-6);
+
+        // past 180 it wraps around to negative
+        for _ in 0..200 {
+            filter.update(&turning, 0.01);
+        }
+        assert!((filter.orientation().yaw + 90.0).abs() < 1e-6);
+
+        filter.reset_yaw();
+        assert_eq!(filter.orientation().yaw, 0.0);
+    }
+}
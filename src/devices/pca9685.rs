@@ -0,0 +1,284 @@
+//! PCA9685, 16-channel 12-bit I2C PWM controller
+
+use crate::utils::lock;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use embedded_hal::PwmPin;
+use std::sync::{Arc, Mutex};
+use std::{thread::sleep, time::Duration};
+use switch_hal::OutputSwitch;
+
+pub const DEFAULT_ADDR: u8 = 0x40;
+pub const ALL_CALL_ADDR: u8 = 0x70;
+pub const CHANNELS: u8 = 16;
+
+/// Counts per PWM period
+pub const MAX_COUNT: u16 = 4096;
+
+const OSC_CLOCK: f64 = 25_000_000.0;
+
+const MODE1: u8 = 0x00;
+const MODE2: u8 = 0x01;
+const ALLCALLADR: u8 = 0x05;
+const LED0_ON_L: u8 = 0x06;
+const ALL_LED_ON_L: u8 = 0xfa;
+const PRE_SCALE: u8 = 0xfe;
+
+const MODE1_RESTART: u8 = 1 << 7;
+const MODE1_AI: u8 = 1 << 5;
+const MODE1_SLEEP: u8 = 1 << 4;
+const MODE1_ALLCALL: u8 = 1 << 0;
+
+const MODE2_INVRT: u8 = 1 << 4;
+const MODE2_OUTDRV: u8 = 1 << 2;
+
+/// Set in the ON or OFF high byte to hold the output fully on or off
+const FULL: u16 = 1 << 12;
+
+pub struct PCA9685<I2C> {
+    i2c: I2C,
+    addr: u8,
+}
+
+impl<I2C, E> PCA9685<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Creates a new `PCA9685`, awake, with every channel off
+    ///
+    /// Outputs are totem pole, non-inverted and respond to the all-call
+    /// address.
+    pub fn new(i2c: I2C, addr: u8) -> Result<Self, E> {
+        let mut pca = Self { i2c, addr };
+
+        pca.write_reg(MODE1, MODE1_AI | MODE1_ALLCALL | MODE1_SLEEP)?;
+        pca.write_reg(MODE2, MODE2_OUTDRV)?;
+        pca.set_all(0, FULL)?;
+        pca.wake()?;
+
+        Ok(pca)
+    }
+
+    /// Sets the PWM frequency (about 24 Hz to 1526 Hz)
+    ///
+    /// Returns the actual frequency after rounding the prescaler.
+    pub fn set_frequency(&mut self, frequency: f64) -> Result<f64, E> {
+        let prescale = (OSC_CLOCK / (f64::from(MAX_COUNT) * frequency)).round() - 1.0;
+        let prescale = prescale.clamp(3.0, 255.0) as u8;
+
+        self.set_prescale(prescale)?;
+
+        Ok(OSC_CLOCK / (f64::from(MAX_COUNT) * (f64::from(prescale) + 1.0)))
+    }
+
+    /// Writes the prescaler, the chip is put to sleep while doing so
+    pub fn set_prescale(&mut self, prescale: u8) -> Result<(), E> {
+        let mode1 = self.read_reg(MODE1)?;
+
+        self.write_reg(MODE1, (mode1 & !MODE1_RESTART) | MODE1_SLEEP)?;
+        self.write_reg(PRE_SCALE, prescale)?;
+        self.write_reg(MODE1, mode1 & !MODE1_RESTART)?;
+
+        if mode1 & MODE1_SLEEP == 0 {
+            self.restart()?;
+        }
+
+        Ok(())
+    }
+
+    /// Sets the counts at which `channel` turns on and off (0..4096)
+    pub fn set_channel(&mut self, channel: u8, on: u16, off: u16) -> Result<(), E> {
+        assert!(channel < CHANNELS, "PCA9685 has 16 channels");
+
+        self.write_counts(LED0_ON_L + 4 * channel, on, off)
+    }
+
+    /// Sets the duty of `channel`, out of [`MAX_COUNT`]
+    pub fn set_duty(&mut self, channel: u8, duty: u16) -> Result<(), E> {
+        let (on, off) = duty_counts(duty);
+        self.set_channel(channel, on, off)
+    }
+
+    /// Sets every channel at once
+    pub fn set_all(&mut self, on: u16, off: u16) -> Result<(), E> {
+        self.write_counts(ALL_LED_ON_L, on, off)
+    }
+
+    /// Enables or disables the all-call address, optionally changing it
+    pub fn set_all_call(&mut self, enabled: bool, addr: Option<u8>) -> Result<(), E> {
+        if let Some(addr) = addr {
+            self.write_reg(ALLCALLADR, addr << 1)?;
+        }
+
+        self.update_reg(MODE1, MODE1_ALLCALL, enabled)
+    }
+
+    /// Inverts the output logic, for LEDs wired to the supply
+    pub fn set_inverted(&mut self, inverted: bool) -> Result<(), E> {
+        self.update_reg(MODE2, MODE2_INVRT, inverted)
+    }
+
+    /// Selects totem pole (`true`) or open drain (`false`) outputs
+    pub fn set_totem_pole(&mut self, totem_pole: bool) -> Result<(), E> {
+        self.update_reg(MODE2, MODE2_OUTDRV, totem_pole)
+    }
+
+    /// Stops the oscillator, outputs are off until [`wake`](Self::wake)
+    pub fn sleep(&mut self) -> Result<(), E> {
+        self.update_reg(MODE1, MODE1_SLEEP, true)
+    }
+
+    /// Wakes from sleep, resuming the previous duty cycles
+    pub fn wake(&mut self) -> Result<(), E> {
+        self.update_reg(MODE1, MODE1_SLEEP, false)?;
+        self.restart()
+    }
+
+    /// Restarts the PWM channels if they were stopped by sleep
+    pub fn restart(&mut self) -> Result<(), E> {
+        let mode1 = self.read_reg(MODE1)?;
+
+        if mode1 & MODE1_RESTART != 0 {
+            self.write_reg(MODE1, mode1 & !(MODE1_SLEEP | MODE1_RESTART))?;
+            // oscillator needs 500 us to stabilise
+            sleep(Duration::from_micros(500));
+            self.write_reg(MODE1, (mode1 & !MODE1_SLEEP) | MODE1_RESTART)?;
+        }
+
+        Ok(())
+    }
+
+    /// Shares the controller so each channel can be a [`PwmChannel`]
+    pub fn into_shared(self) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    fn write_counts(&mut self, reg: u8, on: u16, off: u16) -> Result<(), E> {
+        let on = on.to_le_bytes();
+        let off = off.to_le_bytes();
+
+        self.i2c
+            .write(self.addr, &[reg, on[0], on[1], off[0], off[1]])
+    }
+
+    fn update_reg(&mut self, reg: u8, mask: u8, set: bool) -> Result<(), E> {
+        // never write RESTART back by accident, writing 1 restarts
+        let value = self.read_reg(reg)? & !(if reg == MODE1 { MODE1_RESTART } else { 0 });
+        let value = if set { value | mask } else { value & !mask };
+
+        self.write_reg(reg, value)
+    }
+
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<(), E> {
+        self.i2c.write(self.addr, &[reg, value])
+    }
+
+    fn read_reg(&mut self, reg: u8) -> Result<u8, E> {
+        let mut buf: [u8; 1] = [0];
+        self.i2c.write_read(self.addr, &[reg], &mut buf)?;
+
+        Ok(buf[0])
+    }
+}
+
+/// ON/OFF counts for a duty, using the full on/off bits at the ends
+fn duty_counts(duty: u16) -> (u16, u16) {
+    match duty {
+        0 => (0, FULL),
+        d if d >= MAX_COUNT => (FULL, 0),
+        d => (0, d),
+    }
+}
+
+/// One PCA9685 channel as an embedded-hal `PwmPin`
+///
+/// Duty is out of [`MAX_COUNT`]. `PwmPin` cannot report errors, so I2C
+/// errors are dropped.
+pub struct PwmChannel<I2C> {
+    pca: Arc<Mutex<PCA9685<I2C>>>,
+    channel: u8,
+    duty: u16,
+    enabled: bool,
+}
+
+impl<I2C, E> PwmChannel<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Creates a new `PwmChannel`, disabled
+    pub fn new(pca: &Arc<Mutex<PCA9685<I2C>>>, channel: u8) -> Self {
+        assert!(channel < CHANNELS, "PCA9685 has 16 channels");
+
+        Self {
+            pca: pca.clone(),
+            channel,
+            duty: 0,
+            enabled: false,
+        }
+    }
+
+    fn write(&mut self, duty: u16) {
+        let _ = lock(&self.pca).set_duty(self.channel, duty);
+    }
+}
+
+impl<I2C, E> PwmPin for PwmChannel<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Duty = u16;
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.write(0);
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        self.write(self.duty);
+    }
+
+    fn get_duty(&self) -> u16 {
+        self.duty
+    }
+
+    fn get_max_duty(&self) -> u16 {
+        MAX_COUNT
+    }
+
+    fn set_duty(&mut self, duty: u16) {
+        self.duty = duty.min(MAX_COUNT);
+        if self.enabled {
+            self.write(self.duty);
+        }
+    }
+}
+
+/// The OE pin, gating every output at once
+///
+/// OE is active low, so pass `pin.into_active_low_switch()`.
+pub struct OutputEnable<S> {
+    switch: S,
+}
+
+impl<S> OutputEnable<S>
+where
+    S: OutputSwitch,
+{
+    /// Creates a new `OutputEnable`, outputs enabled
+    pub fn new(mut switch: S) -> Result<Self, S::Error> {
+        switch.on()?;
+        Ok(Self { switch })
+    }
+
+    pub fn enable(&mut self) -> Result<(), S::Error> {
+        self.switch.on()
+    }
+
+    pub fn disable(&mut self) -> Result<(), S::Error> {
+        self.switch.off()
+    }
+}
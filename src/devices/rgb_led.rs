@@ -0,0 +1,278 @@
+//! RGB LED on three PWM channels
+//!
+//! Common anode LEDs light up when the pin is low, give them channels with
+//! inverse polarity, e.g. [`SoftPwm::with_polarity`] or a hardware channel
+//! with [`Polarity::Inverse`](rppal::pwm::Polarity::Inverse). A disabled
+//! channel then stays dark too.
+//!
+//! [`SoftPwm::with_polarity`]: crate::utils::soft_pwm::SoftPwm::with_polarity
+
+use crate::utils::Duty;
+use embedded_hal::PwmPin;
+use std::time::{Duration, Instant};
+
+/// A colour, each channel `0.0..=1.0`
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rgb {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0.0, 0.0, 0.0);
+    pub const WHITE: Rgb = Rgb::new(1.0, 1.0, 1.0);
+
+    pub const fn new(r: f64, g: f64, b: f64) -> Self {
+        Self { r, g, b }
+    }
+
+    /// From hue in degrees, saturation and value `0.0..=1.0`
+    pub fn from_hsv(h: f64, s: f64, v: f64) -> Self {
+        let h = h.rem_euclid(360.0) / 60.0;
+        let s = s.clamp(0.0, 1.0);
+        let v = v.clamp(0.0, 1.0);
+
+        let c = v * s;
+        let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+        let m = v - c;
+
+        let (r, g, b) = match h as u32 {
+            0 => (c, x, 0.0),
+            1 => (x, c, 0.0),
+            2 => (0.0, c, x),
+            3 => (0.0, x, c),
+            4 => (x, 0.0, c),
+            _ => (c, 0.0, x),
+        };
+
+        Self::new(r + m, g + m, b + m)
+    }
+
+    /// White at a colour temperature, 1000 K to 40000 K
+    ///
+    /// Uses Tanner Helland's fit of the black body curve.
+    pub fn from_kelvin(kelvin: f64) -> Self {
+        let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+
+        let r = if t <= 66.0 {
+            255.0
+        } else {
+            329.698_727_446 * (t - 60.0).powf(-0.133_204_759_2)
+        };
+        let g = if t <= 66.0 {
+            99.470_802_586_1 * t.ln() - 161.119_568_166_1
+        } else {
+            288.122_169_528_3 * (t - 60.0).powf(-0.075_514_849_2)
+        };
+        let b = if t >= 66.0 {
+            255.0
+        } else if t <= 19.0 {
+            0.0
+        } else {
+            138.517_731_223_1 * (t - 10.0).ln() - 305.044_792_730_7
+        };
+
+        Self::new(r / 255.0, g / 255.0, b / 255.0).clamped()
+    }
+
+    /// From 8-bit channels
+    pub fn from_u8(r: u8, g: u8, b: u8) -> Self {
+        Self::new(
+            f64::from(r) / 255.0,
+            f64::from(g) / 255.0,
+            f64::from(b) / 255.0,
+        )
+    }
+
+    /// Linear blend, `t` of `0.0` is `self` and `1.0` is `other`
+    pub fn lerp(self, other: Rgb, t: f64) -> Self {
+        let t = t.clamp(0.0, 1.0);
+
+        Self::new(
+            self.r + (other.r - self.r) * t,
+            self.g + (other.g - self.g) * t,
+            self.b + (other.b - self.b) * t,
+        )
+    }
+
+    pub fn scale(self, k: f64) -> Self {
+        Self::new(self.r * k, self.g * k, self.b * k).clamped()
+    }
+
+    pub fn clamped(self) -> Self {
+        Self::new(
+            self.r.clamp(0.0, 1.0),
+            self.g.clamp(0.0, 1.0),
+            self.b.clamp(0.0, 1.0),
+        )
+    }
+}
+
+/// Output correction
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// Per-channel gamma, about 2.2 makes fades look even to the eye
+    pub gamma: [f64; 3],
+    /// Per-channel gain, to make `Rgb::WHITE` actually look white
+    pub white_balance: [f64; 3],
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            gamma: [2.2; 3],
+            white_balance: [1.0; 3],
+        }
+    }
+}
+
+impl Config {
+    /// Duty ratios for `color` after balance and gamma
+    pub fn duty_ratios(&self, color: Rgb) -> [f64; 3] {
+        let color = color.clamped();
+        let mut ratios = [color.r, color.g, color.b];
+
+        for (i, ratio) in ratios.iter_mut().enumerate() {
+            let v = (*ratio * self.white_balance[i]).clamp(0.0, 1.0);
+            *ratio = v.powf(self.gamma[i]);
+        }
+
+        ratios
+    }
+}
+
+struct Fade {
+    from: Rgb,
+    to: Rgb,
+    started: Instant,
+    duration: Duration,
+}
+
+pub struct RgbLed<R, G, B>
+where
+    R: PwmPin,
+    G: PwmPin,
+    B: PwmPin,
+{
+    r: R,
+    g: G,
+    b: B,
+    config: Config,
+    color: Rgb,
+    fade: Option<Fade>,
+}
+
+impl<R, G, B> RgbLed<R, G, B>
+where
+    R: PwmPin,
+    G: PwmPin,
+    B: PwmPin,
+    R::Duty: Duty,
+    G::Duty: Duty,
+    B::Duty: Duty,
+{
+    /// Creates a new `RgbLed`, off
+    pub fn new(mut r: R, mut g: G, mut b: B, config: Config) -> Self {
+        r.enable();
+        g.enable();
+        b.enable();
+
+        let mut led = Self {
+            r,
+            g,
+            b,
+            config,
+            color: Rgb::BLACK,
+            fade: None,
+        };
+        led.write();
+
+        led
+    }
+
+    pub fn color(&self) -> Rgb {
+        self.color
+    }
+
+    pub fn set_config(&mut self, config: Config) {
+        self.config = config;
+        self.write();
+    }
+
+    /// Sets the colour now, cancelling any fade
+    pub fn set_color(&mut self, color: Rgb) {
+        self.fade = None;
+        self.color = color.clamped();
+        self.write();
+    }
+
+    pub fn set_rgb(&mut self, r: f64, g: f64, b: f64) {
+        self.set_color(Rgb::new(r, g, b));
+    }
+
+    pub fn set_hsv(&mut self, h: f64, s: f64, v: f64) {
+        self.set_color(Rgb::from_hsv(h, s, v));
+    }
+
+    /// White at `kelvin`, dimmed to `brightness`
+    pub fn set_kelvin(&mut self, kelvin: f64, brightness: f64) {
+        self.set_color(Rgb::from_kelvin(kelvin).scale(brightness));
+    }
+
+    pub fn off(&mut self) {
+        self.set_color(Rgb::BLACK);
+    }
+
+    /// Starts a cross-fade from the current colour, driven by
+    /// [`tick`](Self::tick)
+    pub fn fade_to(&mut self, color: Rgb, duration: Duration, now: Instant) {
+        self.fade = Some(Fade {
+            from: self.color,
+            to: color.clamped(),
+            started: now,
+            duration,
+        });
+        self.tick(now);
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Updates a running fade, returns `true` while still fading
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let fade = match &self.fade {
+            Some(fade) => fade,
+            None => return false,
+        };
+
+        let elapsed = now.saturating_duration_since(fade.started);
+        let done = elapsed >= fade.duration;
+        let t = if done {
+            1.0
+        } else {
+            elapsed.as_secs_f64() / fade.duration.as_secs_f64()
+        };
+
+        self.color = fade.from.lerp(fade.to, t);
+        if done {
+            self.fade = None;
+        }
+        self.write();
+
+        !done
+    }
+
+    pub fn release(self) -> (R, G, B) {
+        (self.r, self.g, self.b)
+    }
+
+    fn write(&mut self) {
+        let [r, g, b] = self.config.duty_ratios(self.color);
+
+        self.r.set_duty(Duty::from_ratio(self.r.get_max_duty(), r));
+        self.g.set_duty(Duty::from_ratio(self.g.get_max_duty(), g));
+        self.b.set_duty(Duty::from_ratio(self.b.get_max_duty(), b));
+    }
+}
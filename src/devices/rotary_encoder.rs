@@ -0,0 +1,221 @@
+//! Quadrature rotary encoders such as the KY-040
+//!
+//! The push switch is a plain button, see [`button`](super::button).
+
+use embedded_hal::digital::v2::InputPin;
+use std::time::{Duration, Instant};
+
+/// Step direction for each `previous << 2 | current` state pair, zero for
+/// no change and for impossible jumps where a transition was missed
+const TRANSITIONS: [i8; 16] = [0, -1, 1, 0, 1, 0, 0, -1, -1, 0, 0, 1, 0, 1, -1, 0];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Range {
+    Unbounded,
+    /// Stops at `min` and `max`
+    Clamped(i64, i64),
+    /// Goes from `max` to `min` and back, like a menu
+    Wrapping(i64, i64),
+}
+
+impl Range {
+    /// `min` is at most `max`, and the range's length fits an `i64`
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Range::Unbounded => true,
+            Range::Clamped(min, max) => max.checked_sub(min).is_some_and(|d| d >= 0),
+            Range::Wrapping(min, max) => max
+                .checked_sub(min)
+                .and_then(|d| d.checked_add(1))
+                .is_some_and(|len| len > 0),
+        }
+    }
+
+    /// Brings `value` into the range, wide enough that nothing overflows
+    fn fit(&self, value: i128) -> i64 {
+        match *self {
+            Range::Unbounded => value.clamp(i64::MIN.into(), i64::MAX.into()) as i64,
+            Range::Clamped(min, max) => value.clamp(min.into(), max.into()) as i64,
+            Range::Wrapping(min, max) => {
+                let min = i128::from(min);
+                (min + (value - min).rem_euclid(i128::from(max) - min + 1)) as i64
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// Quadrature steps between detents, 4 on the KY-040
+    pub steps_per_detent: u8,
+    /// Swaps the directions, clockwise counts up by default
+    pub reversed: bool,
+    /// Detents closer together than this are accelerated
+    pub fast: Duration,
+    /// Largest change per detent when turning fast, 1 disables acceleration
+    pub max_acceleration: i64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            steps_per_detent: 4,
+            reversed: false,
+            fast: Duration::from_millis(40),
+            max_acceleration: 1,
+        }
+    }
+}
+
+/// A rotary encoder on pins `A` (CLK) and `B` (DT)
+///
+/// [`update`](Self::update) must run often enough to see every edge, about
+/// every millisecond. Bounce on one line moves back and forth between two
+/// states and cancels itself out.
+pub struct RotaryEncoder<A, B, E>
+where
+    A: InputPin<Error = E>,
+    B: InputPin<Error = E>,
+{
+    a: A,
+    b: B,
+    config: Config,
+    state: u8,
+    steps: i16,
+    last_detent: Option<Instant>,
+    value: i64,
+    range: Range,
+}
+
+impl<A, B, E> RotaryEncoder<A, B, E>
+where
+    A: InputPin<Error = E>,
+    B: InputPin<Error = E>,
+{
+    /// Creates a new `RotaryEncoder` at value 0, unbounded
+    pub fn new(a: A, b: B, config: Config) -> Result<Self, E> {
+        let mut encoder = Self {
+            a,
+            b,
+            config,
+            state: 0,
+            steps: 0,
+            last_detent: None,
+            value: 0,
+            range: Range::Unbounded,
+        };
+        encoder.state = encoder.read()?;
+
+        Ok(encoder)
+    }
+
+    /// Sets the range and brings the value into it
+    ///
+    /// # Panics
+    ///
+    /// If the range is not [valid](Range::is_valid).
+    pub fn set_range(&mut self, range: Range) {
+        assert!(range.is_valid(), "invalid range {:?}", range);
+        self.range = range;
+        self.set_value(self.value);
+    }
+
+    pub fn value(&self) -> i64 {
+        self.value
+    }
+
+    pub fn set_value(&mut self, value: i64) {
+        self.value = self.range.fit(value.into());
+    }
+
+    /// Where the value is within its range, `0.0..=1.0`, e.g. in place of
+    /// an ADC reading
+    ///
+    /// Always 0.0 when unbounded.
+    pub fn ratio(&self) -> f64 {
+        match self.range {
+            Range::Unbounded => 0.0,
+            Range::Clamped(min, max) | Range::Wrapping(min, max) if max > min => {
+                (self.value - min) as f64 / (max - min) as f64
+            }
+            _ => 0.0,
+        }
+    }
+
+    /// Reads the pins, returns the detents turned since the last call,
+    /// positive clockwise
+    pub fn update(&mut self, now: Instant) -> Result<i64, E> {
+        let state = self.read()?;
+        if state == self.state {
+            return Ok(0);
+        }
+
+        let step = TRANSITIONS[usize::from(self.state << 2 | state)];
+        self.state = state;
+        self.steps += i16::from(if self.config.reversed { -step } else { step });
+
+        let per_detent = i16::from(self.config.steps_per_detent.max(1));
+        if self.steps.abs() < per_detent {
+            return Ok(0);
+        }
+
+        let dir = i64::from(self.steps.signum());
+        self.steps = 0;
+
+        let delta = dir * self.acceleration(now);
+        self.value = self.range.fit(i128::from(self.value) + i128::from(delta));
+
+        Ok(dir)
+    }
+
+    pub fn release(self) -> (A, B) {
+        (self.a, self.b)
+    }
+
+    /// Change per detent, from 1 up to `max_acceleration` as detents come
+    /// faster
+    fn acceleration(&mut self, now: Instant) -> i64 {
+        let last = self.last_detent.replace(now);
+        let max = self.config.max_acceleration.max(1);
+
+        match last {
+            Some(last) if max > 1 => {
+                let gap = now.saturating_duration_since(last);
+                if gap >= self.config.fast {
+                    1
+                } else {
+                    let speed = self.config.fast.as_secs_f64() / gap.as_secs_f64().max(1e-3);
+                    (speed.round() as i64).clamp(1, max)
+                }
+            }
+            _ => 1,
+        }
+    }
+
+    fn read(&self) -> Result<u8, E> {
+        let a = self.a.is_high()? as u8;
+        let b = self.b.is_high()? as u8;
+        Ok(a << 1 | b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fits_without_overflow() {
+        let wide = Range::Wrapping(0, i64::MAX - 1);
+        assert!(wide.is_valid());
+        assert_eq!(wide.fit(i64::MIN.into()), i64::MAX - 1);
+        assert_eq!(wide.fit(i64::MAX.into()), 0);
+
+        let menu = Range::Wrapping(1, 5);
+        assert_eq!(menu.fit(6), 1);
+        assert_eq!(menu.fit(0), 5);
+        assert_eq!(menu.fit(i64::MIN.into()), 2);
+
+        assert_eq!(Range::Unbounded.fit(i128::from(i64::MAX) + 4), i64::MAX);
+        assert_eq!(Range::Clamped(-10, 10).fit(i64::MIN.into()), -10);
+    }
+}
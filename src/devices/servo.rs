@@ -0,0 +1,289 @@
+//! Hobby servo on any PWM channel
+
+use crate::utils::Duty;
+use embedded_hal::PwmPin;
+use std::fmt;
+use std::time::Duration;
+
+pub mod continuous;
+pub mod motion;
+
+/// Servo calibration
+///
+/// Angles are in degrees of the servo's own range, e.g. `0.0..=180.0` with
+/// `90.0` at the centre.
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// PWM period, the channel must already be running at this period
+    pub period: Duration,
+    pub pulse_width_min: Duration,
+    pub pulse_width_max: Duration,
+    pub angle_min: f64,
+    pub angle_max: f64,
+    /// Added to every angle to centre a badly mounted horn
+    pub trim: f64,
+    /// Soft `(min, max)` limits, requested angles are clamped to these
+    pub limits: (f64, f64),
+    /// Mirrors the angle around the centre
+    pub inverted: bool,
+}
+
+impl Default for Config {
+    /// SG90: 50 Hz, 500us to 2500us for 0 to 180 degrees
+    fn default() -> Self {
+        Self {
+            period: Duration::from_millis(20),
+            pulse_width_min: Duration::from_micros(500),
+            pulse_width_max: Duration::from_micros(2500),
+            angle_min: 0.0,
+            angle_max: 180.0,
+            trim: 0.0,
+            limits: (0.0, 180.0),
+            inverted: false,
+        }
+    }
+}
+
+/// Why a [`Config`] was rejected
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    /// `angle_min` is not below `angle_max`, or it or the trim is not finite
+    AngleRange,
+    /// The soft limits are reversed or not finite
+    Limits,
+    /// The period is zero
+    Period,
+    /// `pulse_width_min` is not below `pulse_width_max`, or the longest pulse
+    /// doesn't fit in the period
+    PulseWidth,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::AngleRange => write!(f, "invalid angle range"),
+            ConfigError::Limits => write!(f, "invalid soft limits"),
+            ConfigError::Period => write!(f, "invalid period"),
+            ConfigError::PulseWidth => write!(f, "invalid pulse width range"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// An angle that is NaN or infinite
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct InvalidAngle(pub f64);
+
+impl fmt::Display for InvalidAngle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid angle: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidAngle {}
+
+impl Config {
+    /// Checks the angle range, soft limits and pulse timing, [`Servo::new`]
+    /// does this for you
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(self.angle_min.is_finite() && self.angle_max.is_finite() && self.trim.is_finite())
+            || self.angle_min >= self.angle_max
+        {
+            return Err(ConfigError::AngleRange);
+        }
+        let (min, max) = self.limits;
+        if !(min.is_finite() && max.is_finite()) || min > max {
+            return Err(ConfigError::Limits);
+        }
+        if self.period.is_zero() {
+            return Err(ConfigError::Period);
+        }
+        if self.pulse_width_min >= self.pulse_width_max || self.pulse_width_max > self.period {
+            return Err(ConfigError::PulseWidth);
+        }
+
+        Ok(())
+    }
+
+    /// The middle of the angle range
+    pub fn center(&self) -> f64 {
+        (self.angle_min + self.angle_max) / 2.0
+    }
+
+    /// Pulse width for `angle`, after limits, inversion and trim
+    ///
+    /// # Panics
+    ///
+    /// If `angle` is NaN.
+    pub fn pulse_width(&self, angle: f64) -> Duration {
+        let angle = self.clamp(angle);
+        let angle = if self.inverted {
+            self.angle_min + self.angle_max - angle
+        } else {
+            angle
+        };
+        let angle = angle + self.trim;
+
+        let ratio = ((angle - self.angle_min) / (self.angle_max - self.angle_min)).clamp(0.0, 1.0);
+        let min = self.pulse_width_min.as_secs_f64();
+        let max = self.pulse_width_max.as_secs_f64();
+
+        Duration::from_secs_f64(min + (max - min) * ratio)
+    }
+
+    /// Clamps `angle` to the soft limits
+    ///
+    /// # Panics
+    ///
+    /// If the limits are invalid, see [`validate`](Self::validate).
+    pub fn clamp(&self, angle: f64) -> f64 {
+        angle.clamp(self.limits.0, self.limits.1)
+    }
+
+    /// Maps `-1.0..=1.0` to an angle, `0.0` being the centre and the ends
+    /// being the soft limits
+    pub fn normalized_to_angle(&self, v: f64) -> f64 {
+        let v = v.clamp(-1.0, 1.0);
+        let center = self.clamp(self.center());
+
+        if v < 0.0 {
+            center + (center - self.limits.0) * v
+        } else {
+            center + (self.limits.1 - center) * v
+        }
+    }
+}
+
+pub struct Servo<PWM>
+where
+    PWM: PwmPin,
+{
+    pwm: PWM,
+    config: Config,
+    angle: f64,
+}
+
+impl<PWM> Servo<PWM>
+where
+    PWM: PwmPin,
+    PWM::Duty: Duty,
+{
+    /// Creates a new `Servo`, centred
+    pub fn new(mut pwm: PWM, config: Config) -> Result<Self, ConfigError> {
+        config.validate()?;
+        pwm.enable();
+
+        let mut servo = Self {
+            pwm,
+            config,
+            angle: config.center(),
+        };
+        servo.center();
+
+        Ok(servo)
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// The last commanded angle, after clamping
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn pulse_width(&self) -> Duration {
+        self.config.pulse_width(self.angle)
+    }
+
+    /// Moves to `angle`, clamped to the soft limits
+    pub fn set_angle(&mut self, angle: f64) -> Result<(), InvalidAngle> {
+        if !angle.is_finite() {
+            return Err(InvalidAngle(angle));
+        }
+        self.write_angle(angle);
+
+        Ok(())
+    }
+
+    /// Sets the angle from `-1.0..=1.0`, see [`Config::normalized_to_angle`]
+    pub fn set_normalized(&mut self, v: f64) -> Result<(), InvalidAngle> {
+        self.set_angle(self.config.normalized_to_angle(v))
+    }
+
+    pub fn center(&mut self) {
+        self.write_angle(self.config.center());
+    }
+
+    /// Like [`set_angle`](Self::set_angle) for an angle known to be finite
+    fn write_angle(&mut self, angle: f64) {
+        self.angle = self.config.clamp(angle);
+
+        let ratio = self.pulse_width().as_secs_f64() / self.config.period.as_secs_f64();
+        let duty = Duty::from_ratio(self.pwm.get_max_duty(), ratio);
+        self.pwm.set_duty(duty);
+    }
+
+    /// Stops sending pulses, most servos then go limp
+    pub fn disable(&mut self) {
+        self.pwm.disable();
+    }
+
+    pub fn enable(&mut self) {
+        self.pwm.enable();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_limits() {
+        let config = Config::default();
+        assert_eq!(config.validate(), Ok(()));
+
+        let reversed = Config {
+            limits: (120.0, 60.0),
+            ..config
+        };
+        assert_eq!(reversed.validate(), Err(ConfigError::Limits));
+
+        let nan = Config {
+            limits: (f64::NAN, 180.0),
+            ..config
+        };
+        assert_eq!(nan.validate(), Err(ConfigError::Limits));
+
+        let empty = Config {
+            angle_max: 0.0,
+            ..config
+        };
+        assert_eq!(empty.validate(), Err(ConfigError::AngleRange));
+    }
+
+    #[test]
+    fn validates_pulse_timing() {
+        let config = Config::default();
+
+        let zero = Config {
+            period: Duration::ZERO,
+            ..config
+        };
+        assert_eq!(zero.validate(), Err(ConfigError::Period));
+
+        let reversed = Config {
+            pulse_width_min: Duration::from_micros(2500),
+            pulse_width_max: Duration::from_micros(500),
+            ..config
+        };
+        assert_eq!(reversed.validate(), Err(ConfigError::PulseWidth));
+
+        let too_long = Config {
+            period: Duration::from_millis(2),
+            ..config
+        };
+        assert_eq!(too_long.validate(), Err(ConfigError::PulseWidth));
+    }
+}
@@ -0,0 +1,134 @@
+//! Continuous rotation servo, e.g. FS90R
+
+use crate::devices::motor::Drive;
+use crate::utils::Duty;
+use embedded_hal::PwmPin;
+use std::convert::Infallible;
+use std::time::Duration;
+
+/// Continuous servo calibration
+///
+/// The servo stops at `neutral`. Pulses within `deadband` of neutral don't
+/// turn it, so any non-zero speed starts just outside the deadband and
+/// reaches full speed at `neutral ± span`.
+#[derive(Copy, Clone, Debug)]
+pub struct Config {
+    /// PWM period, the channel must already be running at this period
+    pub period: Duration,
+    pub neutral: Duration,
+    pub deadband: Duration,
+    pub span: Duration,
+    /// Swaps the direction, for a wheel mounted on the other side
+    pub inverted: bool,
+}
+
+impl Default for Config {
+    /// FS90R: 50 Hz, stopped at 1500us, full speed at 1500us ± 500us
+    fn default() -> Self {
+        Self {
+            period: Duration::from_millis(20),
+            neutral: Duration::from_micros(1500),
+            deadband: Duration::from_micros(20),
+            span: Duration::from_micros(500),
+            inverted: false,
+        }
+    }
+}
+
+impl Config {
+    /// Pulse width for `speed` (`-1.0..=1.0`)
+    pub fn pulse_width(&self, speed: f64) -> Duration {
+        let speed = speed.clamp(-1.0, 1.0);
+        let speed = if self.inverted { -speed } else { speed };
+
+        if speed == 0.0 {
+            return self.neutral;
+        }
+
+        let deadband = self.deadband.as_secs_f64();
+        let offset = deadband + (self.span.as_secs_f64() - deadband) * speed.abs();
+        let pulse = self.neutral.as_secs_f64() + offset.copysign(speed);
+
+        Duration::from_secs_f64(pulse.max(0.0))
+    }
+}
+
+pub struct ContinuousServo<PWM>
+where
+    PWM: PwmPin,
+{
+    pwm: PWM,
+    config: Config,
+    speed: f64,
+}
+
+impl<PWM> ContinuousServo<PWM>
+where
+    PWM: PwmPin,
+    PWM::Duty: Duty,
+{
+    /// Creates a new `ContinuousServo`, stopped
+    pub fn new(mut pwm: PWM, config: Config) -> Self {
+        pwm.enable();
+
+        let mut servo = Self {
+            pwm,
+            config,
+            speed: 0.0,
+        };
+        servo.write();
+
+        servo
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    /// Moves the stop point, use when the servo creeps at speed `0.0`
+    pub fn set_neutral(&mut self, neutral: Duration) {
+        self.config.neutral = neutral;
+        self.write();
+    }
+
+    pub fn set_deadband(&mut self, deadband: Duration) {
+        self.config.deadband = deadband;
+        self.write();
+    }
+
+    /// Stops sending pulses, the servo stops and goes limp
+    pub fn disable(&mut self) {
+        self.pwm.disable();
+    }
+
+    pub fn enable(&mut self) {
+        self.pwm.enable();
+    }
+
+    fn write(&mut self) {
+        let pulse = self.config.pulse_width(self.speed);
+        let ratio = pulse.as_secs_f64() / self.config.period.as_secs_f64();
+        let duty = Duty::from_ratio(self.pwm.get_max_duty(), ratio);
+
+        self.pwm.set_duty(duty);
+    }
+}
+
+impl<PWM> Drive for ContinuousServo<PWM>
+where
+    PWM: PwmPin,
+    PWM::Duty: Duty,
+{
+    type Error = Infallible;
+
+    fn set_speed(&mut self, speed: f64) -> Result<(), Infallible> {
+        self.speed = speed.clamp(-1.0, 1.0);
+        self.write();
+
+        Ok(())
+    }
+}
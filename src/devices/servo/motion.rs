@@ -0,0 +1,159 @@
+//! Speed-limited servo motion
+
+use super::{InvalidAngle, Servo};
+use crate::utils::Duty;
+use embedded_hal::PwmPin;
+use std::time::{Duration, Instant};
+
+pub use crate::animation::Easing;
+
+/// Moves a [`Servo`] toward a target at a limited angular velocity
+///
+/// Nothing blocks: call [`tick`](Self::tick) regularly from the control loop
+/// and the servo is stepped along its path.
+pub struct ServoMotion<PWM>
+where
+    PWM: PwmPin,
+{
+    servo: Servo<PWM>,
+    max_speed: f64,
+    easing: Easing,
+    from: f64,
+    target: f64,
+    started: Instant,
+    duration: Duration,
+    sweep: Option<(f64, f64)>,
+}
+
+impl<PWM> ServoMotion<PWM>
+where
+    PWM: PwmPin,
+    PWM::Duty: Duty,
+{
+    /// Creates a new `ServoMotion`, `max_speed` is in degrees per second
+    ///
+    /// A `max_speed` that isn't positive, or is NaN, moves instantly.
+    pub fn new(servo: Servo<PWM>, max_speed: f64, easing: Easing) -> Self {
+        let angle = servo.angle();
+
+        Self {
+            servo,
+            max_speed,
+            easing,
+            from: angle,
+            target: angle,
+            started: Instant::now(),
+            duration: Duration::default(),
+            sweep: None,
+        }
+    }
+
+    pub fn servo(&self) -> &Servo<PWM> {
+        &self.servo
+    }
+
+    pub fn servo_mut(&mut self) -> &mut Servo<PWM> {
+        &mut self.servo
+    }
+
+    pub fn into_inner(self) -> Servo<PWM> {
+        self.servo
+    }
+
+    pub fn set_max_speed(&mut self, max_speed: f64) {
+        self.max_speed = max_speed;
+    }
+
+    pub fn set_easing(&mut self, easing: Easing) {
+        self.easing = easing;
+    }
+
+    pub fn target(&self) -> f64 {
+        self.target
+    }
+
+    pub fn is_moving(&self) -> bool {
+        self.servo.angle() != self.target
+    }
+
+    /// Starts moving toward `angle` from wherever the servo is now
+    ///
+    /// Cancels a running sweep.
+    pub fn set_target(&mut self, angle: f64, now: Instant) -> Result<(), InvalidAngle> {
+        if !angle.is_finite() {
+            return Err(InvalidAngle(angle));
+        }
+        self.sweep = None;
+        self.start(angle, now);
+
+        Ok(())
+    }
+
+    /// Sets the target from `-1.0..=1.0`, like [`Servo::set_normalized`]
+    pub fn set_target_normalized(&mut self, v: f64, now: Instant) -> Result<(), InvalidAngle> {
+        let angle = self.servo.config().normalized_to_angle(v);
+        self.set_target(angle, now)
+    }
+
+    /// Sweeps back and forth between `min` and `max` until a new target is
+    /// set or [`stop`](Self::stop) is called
+    pub fn sweep(&mut self, min: f64, max: f64, now: Instant) -> Result<(), InvalidAngle> {
+        if let Some(&bad) = [min, max].iter().find(|a| !a.is_finite()) {
+            return Err(InvalidAngle(bad));
+        }
+        let config = self.servo.config();
+        let (min, max) = (config.clamp(min), config.clamp(max));
+
+        self.sweep = Some((min, max));
+        self.start(min, now);
+
+        Ok(())
+    }
+
+    /// Stops where the servo is now
+    pub fn stop(&mut self, now: Instant) {
+        self.sweep = None;
+        self.start(self.servo.angle(), now);
+    }
+
+    /// Updates the servo position, returns `true` while still moving
+    pub fn tick(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.started);
+
+        if elapsed >= self.duration {
+            if self.servo.angle() != self.target {
+                self.servo.write_angle(self.target);
+            }
+
+            match self.sweep {
+                Some((min, max)) => {
+                    let next = if self.target == min { max } else { min };
+                    self.start(next, now);
+                    true
+                }
+                None => false,
+            }
+        } else {
+            let t = elapsed.as_secs_f64() / self.duration.as_secs_f64();
+            let angle = self.from + (self.target - self.from) * self.easing.apply(t);
+            self.servo.write_angle(angle);
+            true
+        }
+    }
+
+    fn start(&mut self, angle: f64, now: Instant) {
+        let target = self.servo.config().clamp(angle);
+        let from = self.servo.angle();
+        let secs = if self.max_speed > 0.0 {
+            (target - from).abs() / self.max_speed * self.easing.peak_slope()
+        } else {
+            0.0
+        };
+
+        self.from = from;
+        self.target = target;
+        self.started = now;
+        // a speed too low to represent never gets there rather than panicking
+        self.duration = Duration::try_from_secs_f64(secs).unwrap_or(Duration::MAX);
+    }
+}
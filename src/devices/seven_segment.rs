@@ -0,0 +1,256 @@
+//! Multiplexed 7-segment displays
+//!
+//! Segment patterns are `0bPGFEDCBA`, bit 0 being segment A and bit 7 the
+//! decimal point.
+
+use std::time::{Duration, Instant};
+use switch_hal::OutputSwitch;
+
+pub const DP: u8 = 1 << 7;
+pub const MINUS: u8 = 0b0100_0000;
+pub const BLANK: u8 = 0;
+
+/// 0 to F
+pub const HEX: [u8; 16] = [
+    0b0011_1111,
+    0b0000_0110,
+    0b0101_1011,
+    0b0100_1111,
+    0b0110_0110,
+    0b0110_1101,
+    0b0111_1101,
+    0b0000_0111,
+    0b0111_1111,
+    0b0110_1111,
+    0b0111_0111,
+    0b0111_1100,
+    0b0011_1001,
+    0b0101_1110,
+    0b0111_1001,
+    0b0111_0001,
+];
+
+/// Best effort pattern for a character, blank when there is none
+pub fn char_segments(c: char) -> u8 {
+    match c.to_ascii_uppercase() {
+        c @ '0'..='9' => HEX[c as usize - '0' as usize],
+        'A' => HEX[10],
+        'B' => HEX[11],
+        'C' => HEX[12],
+        'D' => HEX[13],
+        'E' => HEX[14],
+        'F' => HEX[15],
+        'G' => 0b0011_1101,
+        'H' => 0b0111_0110,
+        'I' => 0b0000_0110,
+        'J' => 0b0001_1110,
+        'L' => 0b0011_1000,
+        'N' => 0b0101_0100,
+        'O' => 0b0101_1100,
+        'P' => 0b0111_0011,
+        'Q' => 0b0110_0111,
+        'R' => 0b0101_0000,
+        'S' => HEX[5],
+        'T' => 0b0111_1000,
+        'U' => 0b0011_1110,
+        'Y' => 0b0110_1110,
+        'Z' => HEX[2],
+        '-' => MINUS,
+        '_' => 0b0000_1000,
+        '=' => 0b0100_1000,
+        '"' => 0b0010_0010,
+        '\'' => 0b0000_0010,
+        '[' => HEX[12],
+        ']' => 0b0000_1111,
+        _ => BLANK,
+    }
+}
+
+/// Converts text to patterns, folding each `.` into the previous digit
+pub fn text_segments(text: &str) -> Vec<u8> {
+    let mut segments: Vec<u8> = Vec::with_capacity(text.len());
+
+    for c in text.chars() {
+        match (c, segments.last_mut()) {
+            ('.', Some(last)) if *last & DP == 0 => *last |= DP,
+            ('.', _) => segments.push(DP),
+            (c, _) => segments.push(char_segments(c)),
+        }
+    }
+
+    segments
+}
+
+struct Scroll {
+    segments: Vec<u8>,
+    step: Duration,
+    started: Instant,
+}
+
+/// A `D` digit display, digit 0 being the leftmost
+///
+/// `D` is 1 to 8, anything else fails to compile.
+///
+/// Only one digit is lit at a time: call [`refresh`](Self::refresh) (or
+/// [`next_frame`](Self::next_frame) for other outputs) often enough that
+/// every digit is shown at least 60 times a second.
+///
+/// The lines are [`OutputSwitch`]es, so the wiring's polarity is declared
+/// where they are made: a common cathode display driven directly has
+/// active high segments and active low digits, a common anode one the
+/// other way around.
+pub struct SevenSegment<const D: usize> {
+    segments: [u8; D],
+    current: usize,
+    scroll: Option<Scroll>,
+}
+
+impl<const D: usize> Default for SevenSegment<D> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const D: usize> SevenSegment<D> {
+    /// Digits are selected by the bits of a `u8`
+    const DIGITS_OK: () = assert!(D >= 1 && D <= 8, "a display has 1 to 8 digits");
+
+    pub fn new() -> Self {
+        let () = Self::DIGITS_OK;
+
+        Self {
+            segments: [BLANK; D],
+            current: 0,
+            scroll: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.scroll = None;
+        self.segments = [BLANK; D];
+    }
+
+    /// Sets the raw pattern of digit `pos`
+    pub fn set_segments(&mut self, pos: usize, segments: u8) {
+        self.scroll = None;
+        self.segments[pos] = segments;
+    }
+
+    /// Shows `value` (0..=15) at `pos`
+    pub fn set_digit(&mut self, pos: usize, value: u8, dp: bool) {
+        let segments = HEX[usize::from(value & 0xf)];
+        self.set_segments(pos, if dp { segments | DP } else { segments });
+    }
+
+    /// Shows text left aligned, cut to fit
+    pub fn show_str(&mut self, text: &str) {
+        let segments = text_segments(text);
+        self.scroll = None;
+        fill(&mut self.segments, &segments, 0);
+    }
+
+    /// Shows `value` right aligned, dashes when it doesn't fit
+    pub fn show_number(&mut self, value: i64) {
+        self.show_right(&value.to_string());
+    }
+
+    /// Shows `value` right aligned with `decimals` digits after the point
+    pub fn show_float(&mut self, value: f64, decimals: usize) {
+        self.show_right(&format!("{:.*}", decimals, value));
+    }
+
+    /// Shows `value` in hex, right aligned
+    pub fn show_hex(&mut self, value: u32) {
+        self.show_right(&format!("{:X}", value));
+    }
+
+    /// Scrolls `text` right to left, one digit every `step`
+    pub fn scroll_text(&mut self, text: &str, step: Duration, now: Instant) {
+        let mut segments = vec![BLANK; D];
+        segments.extend(text_segments(text));
+
+        self.scroll = Some(Scroll {
+            segments,
+            step,
+            started: now,
+        });
+        self.tick(now);
+    }
+
+    pub fn is_scrolling(&self) -> bool {
+        self.scroll.is_some()
+    }
+
+    /// Advances scrolling text
+    pub fn tick(&mut self, now: Instant) {
+        if let Some(scroll) = &self.scroll {
+            let steps = now.saturating_duration_since(scroll.started).as_secs_f64()
+                / scroll.step.as_secs_f64();
+            let offset = steps as usize % scroll.segments.len();
+            fill(&mut self.segments, &scroll.segments, offset);
+        }
+    }
+
+    /// Returns the lit segments and the selected digit for the next frame
+    ///
+    /// Bit `i` of the digit byte selects digit `i`, a set bit is on.
+    pub fn next_frame(&mut self) -> (u8, u8) {
+        let pos = self.advance();
+        (self.segments[pos], 1u8 << pos)
+    }
+
+    /// Shows the next digit
+    ///
+    /// `segments` are A..G and DP, `digits` select digit 0 to `D - 1`.
+    pub fn refresh<SEG, DIG, E>(
+        &mut self,
+        segments: &mut [SEG; 8],
+        digits: &mut [DIG; D],
+    ) -> Result<(), E>
+    where
+        SEG: OutputSwitch<Error = E>,
+        DIG: OutputSwitch<Error = E>,
+    {
+        let pos = self.advance();
+
+        // deselect first, the new pattern must not flash on the old digit
+        for digit in digits.iter_mut() {
+            digit.off()?;
+        }
+        for (i, segment) in segments.iter_mut().enumerate() {
+            if self.segments[pos] & 1 << i != 0 {
+                segment.on()?;
+            } else {
+                segment.off()?;
+            }
+        }
+
+        digits[pos].on()
+    }
+
+    /// Moves on to the next digit, returns the one to show
+    fn advance(&mut self) -> usize {
+        let pos = self.current;
+        self.current = (self.current + 1) % D;
+        pos
+    }
+
+    fn show_right(&mut self, text: &str) {
+        let segments = text_segments(text);
+        self.scroll = None;
+
+        if segments.len() > D {
+            self.segments = [MINUS; D];
+        } else {
+            self.segments = [BLANK; D];
+            self.segments[D - segments.len()..].copy_from_slice(&segments);
+        }
+    }
+}
+
+/// Copies `src` from `offset` into `dst`, blanking past its end
+fn fill(dst: &mut [u8], src: &[u8], offset: usize) {
+    for (i, s) in dst.iter_mut().enumerate() {
+        *s = src.get(offset + i).copied().unwrap_or(BLANK);
+    }
+}
@@ -0,0 +1,226 @@
+//! SSD1306 monochrome OLED with an `embedded-graphics` frame buffer
+//!
+//! Draw with `embedded-graphics` (or [`SSD1306::set_pixel`]), then send the
+//! frame with [`SSD1306::flush`].
+
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::{DrawTarget, OriginDimensions, Pixel, Size};
+use std::convert::Infallible;
+
+pub mod interface;
+pub mod telemetry;
+
+pub use interface::{I2cInterface, Interface, SpiInterface, DEFAULT_ADDR};
+
+const SET_CONTRAST: u8 = 0x81;
+const ENTIRE_DISPLAY_RESUME: u8 = 0xa4;
+const NORMAL_DISPLAY: u8 = 0xa6;
+const INVERT_DISPLAY: u8 = 0xa7;
+const DISPLAY_OFF: u8 = 0xae;
+const DISPLAY_ON: u8 = 0xaf;
+const SET_MEMORY_MODE: u8 = 0x20;
+const SET_COLUMN_ADDR: u8 = 0x21;
+const SET_PAGE_ADDR: u8 = 0x22;
+const DEACTIVATE_SCROLL: u8 = 0x2e;
+const SET_START_LINE: u8 = 0x40;
+const SEG_REMAP: u8 = 0xa0;
+const SET_MULTIPLEX: u8 = 0xa8;
+const COM_SCAN_INC: u8 = 0xc0;
+const COM_SCAN_DEC: u8 = 0xc8;
+const SET_DISPLAY_OFFSET: u8 = 0xd3;
+const SET_CLOCK_DIV: u8 = 0xd5;
+const SET_PRECHARGE: u8 = 0xd9;
+const SET_COM_PINS: u8 = 0xda;
+const SET_VCOM_DETECT: u8 = 0xdb;
+const CHARGE_PUMP: u8 = 0x8d;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DisplaySize {
+    Display128x64,
+    Display128x32,
+}
+
+impl DisplaySize {
+    pub fn width(self) -> u32 {
+        128
+    }
+
+    pub fn height(self) -> u32 {
+        match self {
+            DisplaySize::Display128x64 => 64,
+            DisplaySize::Display128x32 => 32,
+        }
+    }
+
+    fn com_pins(self) -> u8 {
+        match self {
+            DisplaySize::Display128x64 => 0x12,
+            DisplaySize::Display128x32 => 0x02,
+        }
+    }
+}
+
+/// An SSD1306 with its internal charge pump enabled
+///
+/// Pixel (0, 0) is the top left corner with the connector on top.
+pub struct SSD1306<DI> {
+    interface: DI,
+    size: DisplaySize,
+    /// One byte per column of each 8 pixel high page, bit 0 on top
+    buffer: Vec<u8>,
+}
+
+impl<DI, E> SSD1306<DI>
+where
+    DI: Interface<Error = E>,
+{
+    /// Creates a new `SSD1306`, cleared and switched on
+    pub fn new(interface: DI, size: DisplaySize) -> Result<Self, E> {
+        let mut display = Self {
+            interface,
+            size,
+            buffer: vec![0; (size.width() * size.height() / 8) as usize],
+        };
+
+        display.interface.command(&[
+            DISPLAY_OFF,
+            SET_CLOCK_DIV,
+            0x80,
+            SET_MULTIPLEX,
+            (size.height() - 1) as u8,
+            SET_DISPLAY_OFFSET,
+            0,
+            SET_START_LINE,
+            CHARGE_PUMP,
+            0x14,
+            // horizontal addressing, the whole frame goes out in one write
+            SET_MEMORY_MODE,
+            0x00,
+            SEG_REMAP | 1,
+            COM_SCAN_DEC,
+            SET_COM_PINS,
+            size.com_pins(),
+            SET_CONTRAST,
+            0xcf,
+            SET_PRECHARGE,
+            0xf1,
+            SET_VCOM_DETECT,
+            0x40,
+            ENTIRE_DISPLAY_RESUME,
+            NORMAL_DISPLAY,
+            DEACTIVATE_SCROLL,
+        ])?;
+        display.flush()?;
+        display.interface.command(&[DISPLAY_ON])?;
+
+        Ok(display)
+    }
+
+    pub fn display_size(&self) -> DisplaySize {
+        self.size
+    }
+
+    /// Out of range pixels are ignored
+    pub fn set_pixel(&mut self, x: u32, y: u32, on: bool) {
+        if x >= self.size.width() || y >= self.size.height() {
+            return;
+        }
+
+        let i = (y / 8 * self.size.width() + x) as usize;
+        let bit = 1 << (y % 8);
+        if on {
+            self.buffer[i] |= bit;
+        } else {
+            self.buffer[i] &= !bit;
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> bool {
+        x < self.size.width()
+            && y < self.size.height()
+            && self.buffer[(y / 8 * self.size.width() + x) as usize] & (1 << (y % 8)) != 0
+    }
+
+    /// Clears the frame buffer, call [`flush`](Self::flush) to show it
+    pub fn clear_buffer(&mut self) {
+        self.buffer.fill(0);
+    }
+
+    /// Sends the frame buffer to the display
+    pub fn flush(&mut self) -> Result<(), E> {
+        let pages = (self.size.height() / 8) as u8;
+
+        self.interface.command(&[
+            SET_COLUMN_ADDR,
+            0,
+            (self.size.width() - 1) as u8,
+            SET_PAGE_ADDR,
+            0,
+            pages - 1,
+        ])?;
+        self.interface.data(&self.buffer)
+    }
+
+    pub fn set_contrast(&mut self, contrast: u8) -> Result<(), E> {
+        self.interface.command(&[SET_CONTRAST, contrast])
+    }
+
+    /// Turns the panel off, the frame is kept
+    pub fn set_display_on(&mut self, on: bool) -> Result<(), E> {
+        self.interface
+            .command(&[if on { DISPLAY_ON } else { DISPLAY_OFF }])
+    }
+
+    /// Shows lit pixels dark and vice versa
+    pub fn set_inverted(&mut self, inverted: bool) -> Result<(), E> {
+        self.interface.command(&[if inverted {
+            INVERT_DISPLAY
+        } else {
+            NORMAL_DISPLAY
+        }])
+    }
+
+    /// Rotates the picture by 180 degrees, for modules mounted upside down
+    pub fn set_flipped(&mut self, flipped: bool) -> Result<(), E> {
+        if flipped {
+            self.interface.command(&[SEG_REMAP, COM_SCAN_INC])
+        } else {
+            self.interface.command(&[SEG_REMAP | 1, COM_SCAN_DEC])
+        }
+    }
+
+    pub fn release(self) -> DI {
+        self.interface
+    }
+}
+
+impl<DI> OriginDimensions for SSD1306<DI> {
+    fn size(&self) -> Size {
+        Size::new(self.size.width(), self.size.height())
+    }
+}
+
+impl<DI, E> DrawTarget for SSD1306<DI>
+where
+    DI: Interface<Error = E>,
+{
+    type Color = BinaryColor;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Infallible>
+    where
+        I: IntoIterator<Item = Pixel<BinaryColor>>,
+    {
+        for Pixel(point, color) in pixels {
+            if point.x >= 0 && point.y >= 0 {
+                self.set_pixel(point.x as u32, point.y as u32, color.is_on());
+            }
+        }
+        Ok(())
+    }
+
+    fn clear(&mut self, color: BinaryColor) -> Result<(), Infallible> {
+        self.buffer.fill(if color.is_on() { 0xff } else { 0 });
+        Ok(())
+    }
+}
@@ -0,0 +1,115 @@
+//! I2C and 4-wire SPI connections
+
+use embedded_hal::blocking::{i2c, spi};
+use embedded_hal::digital::v2::OutputPin;
+use std::fmt;
+
+/// Address with SA0 low, `0x3d` with it high
+pub const DEFAULT_ADDR: u8 = 0x3c;
+
+/// Sends commands and display data
+pub trait Interface {
+    type Error;
+
+    fn command(&mut self, commands: &[u8]) -> Result<(), Self::Error>;
+    fn data(&mut self, data: &[u8]) -> Result<(), Self::Error>;
+}
+
+pub struct I2cInterface<I2C> {
+    i2c: I2C,
+    addr: u8,
+    buf: Vec<u8>,
+}
+
+impl<I2C, E> I2cInterface<I2C>
+where
+    I2C: i2c::Write<Error = E>,
+{
+    pub fn new(i2c: I2C, addr: u8) -> Self {
+        Self {
+            i2c,
+            addr,
+            buf: Vec::new(),
+        }
+    }
+
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Every transfer starts with a control byte telling commands from data
+    fn write(&mut self, control: u8, bytes: &[u8]) -> Result<(), E> {
+        self.buf.clear();
+        self.buf.push(control);
+        self.buf.extend_from_slice(bytes);
+        self.i2c.write(self.addr, &self.buf)
+    }
+}
+
+impl<I2C, E> Interface for I2cInterface<I2C>
+where
+    I2C: i2c::Write<Error = E>,
+{
+    type Error = E;
+
+    fn command(&mut self, commands: &[u8]) -> Result<(), E> {
+        self.write(0x00, commands)
+    }
+
+    fn data(&mut self, data: &[u8]) -> Result<(), E> {
+        self.write(0x40, data)
+    }
+}
+
+#[derive(Debug)]
+pub enum SpiError<S, P> {
+    Spi(S),
+    Dc(P),
+}
+
+impl<S: fmt::Debug, P: fmt::Debug> fmt::Display for SpiError<S, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpiError::Spi(e) => write!(f, "SPI error: {:?}", e),
+            SpiError::Dc(e) => write!(f, "D/C pin error: {:?}", e),
+        }
+    }
+}
+
+impl<S: fmt::Debug, P: fmt::Debug> std::error::Error for SpiError<S, P> {}
+
+/// SPI with a D/C pin, low for commands and high for data
+///
+/// CS is handled by the SPI bus. RES needs to be high, e.g. tied to VCC.
+pub struct SpiInterface<SPI, DC> {
+    spi: SPI,
+    dc: DC,
+}
+
+impl<SPI, DC> SpiInterface<SPI, DC> {
+    pub fn new(spi: SPI, dc: DC) -> Self {
+        Self { spi, dc }
+    }
+
+    pub fn release(self) -> (SPI, DC) {
+        (self.spi, self.dc)
+    }
+}
+
+impl<SPI, DC, S, P> Interface for SpiInterface<SPI, DC>
+where
+    SPI: spi::Write<u8, Error = S>,
+    DC: OutputPin<Error = P>,
+{
+    type Error = SpiError<S, P>;
+
+    fn command(&mut self, commands: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_low().map_err(SpiError::Dc)?;
+        self.spi.write(commands).map_err(SpiError::Spi)
+    }
+
+    fn data(&mut self, data: &[u8]) -> Result<(), Self::Error> {
+        self.dc.set_high().map_err(SpiError::Dc)?;
+        self.spi.write(data).map_err(SpiError::Spi)
+    }
+}
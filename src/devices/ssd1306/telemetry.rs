@@ -0,0 +1,251 @@
+//! Pages of live values for small monochrome displays
+//!
+//! The application describes pages of fields and gauges once, pushes new
+//! values by key as they change and draws the current page whenever it
+//! refreshes the display.
+
+use embedded_graphics::mono_font::{ascii::FONT_6X10, MonoTextStyle};
+use embedded_graphics::pixelcolor::BinaryColor;
+use embedded_graphics::prelude::*;
+use embedded_graphics::primitives::{PrimitiveStyle, Rectangle};
+use embedded_graphics::text::{Alignment, Baseline, Text, TextStyleBuilder};
+
+const LINE_HEIGHT: i32 = 11;
+/// Where gauges start, leaves room for a short label
+const GAUGE_X: i32 = 36;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Text(String),
+    /// Shown as `--`, e.g. before the first reading
+    Missing,
+}
+
+#[derive(Clone, Debug)]
+enum Widget {
+    Field { unit: String, decimals: usize },
+    Gauge { min: f64, max: f64 },
+}
+
+#[derive(Clone, Debug)]
+struct Row {
+    key: String,
+    widget: Widget,
+    value: Value,
+}
+
+/// A title and a row per value
+#[derive(Clone, Debug)]
+pub struct Page {
+    title: String,
+    rows: Vec<Row>,
+}
+
+impl Page {
+    pub fn new(title: &str) -> Self {
+        Self {
+            title: title.to_string(),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Adds a `key: value unit` row, numbers show `decimals` digits
+    pub fn field(self, key: &str, unit: &str, decimals: usize) -> Self {
+        self.row(
+            key,
+            Widget::Field {
+                unit: unit.to_string(),
+                decimals,
+            },
+        )
+    }
+
+    /// Adds a bar gauge from `min` to `max`
+    ///
+    /// When the range spans zero the bar grows from the zero point, e.g.
+    /// for throttle or steering.
+    ///
+    /// # Panics
+    ///
+    /// If `min` is not below `max`, or either is not finite.
+    pub fn gauge(self, key: &str, min: f64, max: f64) -> Self {
+        assert!(
+            min.is_finite() && max.is_finite() && min < max,
+            "gauge range must be finite and increasing"
+        );
+
+        self.row(key, Widget::Gauge { min, max })
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    fn row(mut self, key: &str, widget: Widget) -> Self {
+        self.rows.push(Row {
+            key: key.to_string(),
+            widget,
+            value: Value::Missing,
+        });
+        self
+    }
+}
+
+/// A set of pages, one shown at a time
+#[derive(Clone, Debug, Default)]
+pub struct Telemetry {
+    pages: Vec<Page>,
+    current: usize,
+}
+
+impl Telemetry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_page(&mut self, page: Page) {
+        self.pages.push(page);
+    }
+
+    /// Sets `key` on every page showing it, unknown keys are ignored
+    pub fn set(&mut self, key: &str, value: Value) {
+        for row in self.pages.iter_mut().flat_map(|p| p.rows.iter_mut()) {
+            if row.key == key {
+                row.value = value.clone();
+            }
+        }
+    }
+
+    pub fn set_number(&mut self, key: &str, value: f64) {
+        self.set(key, Value::Number(value));
+    }
+
+    pub fn set_text(&mut self, key: &str, value: &str) {
+        self.set(key, Value::Text(value.to_string()));
+    }
+
+    pub fn current_page(&self) -> usize {
+        self.current
+    }
+
+    pub fn set_page(&mut self, page: usize) {
+        if page < self.pages.len() {
+            self.current = page;
+        }
+    }
+
+    /// Moves to the next page, wrapping around
+    pub fn next_page(&mut self) {
+        if !self.pages.is_empty() {
+            self.current = (self.current + 1) % self.pages.len();
+        }
+    }
+
+    /// Clears `target` and draws the current page on it
+    ///
+    /// The title is drawn inverted on the first line. Rows that don't fit
+    /// are left out.
+    pub fn draw<D>(&self, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        target.clear(BinaryColor::Off)?;
+
+        let page = match self.pages.get(self.current) {
+            Some(page) => page,
+            None => return Ok(()),
+        };
+
+        let width = target.bounding_box().size.width as i32;
+        let height = target.bounding_box().size.height as i32;
+        let text = MonoTextStyle::new(&FONT_6X10, BinaryColor::On);
+        let inverted = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+
+        Rectangle::new(
+            Point::zero(),
+            Size::new(width as u32, LINE_HEIGHT as u32 - 1),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)?;
+        Text::with_baseline(&page.title, Point::new(1, 0), inverted, Baseline::Top).draw(target)?;
+
+        let right = TextStyleBuilder::new()
+            .alignment(Alignment::Right)
+            .baseline(Baseline::Top)
+            .build();
+
+        for (i, row) in page.rows.iter().enumerate() {
+            let y = LINE_HEIGHT * (i as i32 + 1);
+            if y + LINE_HEIGHT - 1 > height {
+                break;
+            }
+
+            Text::with_baseline(&row.key, Point::new(0, y), text, Baseline::Top).draw(target)?;
+
+            match (&row.widget, &row.value) {
+                (Widget::Gauge { min, max }, Value::Number(v)) => {
+                    draw_gauge(target, y, width, *v, *min, *max)?;
+                }
+                (widget, value) => {
+                    let s = format_value(widget, value);
+                    Text::with_text_style(&s, Point::new(width - 1, y), text, right)
+                        .draw(target)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn format_value(widget: &Widget, value: &Value) -> String {
+    match (widget, value) {
+        (Widget::Field { unit, decimals }, Value::Number(v)) => {
+            format!("{:.*}{}", decimals, v, unit)
+        }
+        (_, Value::Number(v)) => format!("{}", v),
+        (_, Value::Text(s)) => s.clone(),
+        (_, Value::Missing) => "--".to_string(),
+    }
+}
+
+fn draw_gauge<D>(
+    target: &mut D,
+    y: i32,
+    width: i32,
+    value: f64,
+    min: f64,
+    max: f64,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    let bar_width = width - GAUGE_X;
+    let outline = Rectangle::new(
+        Point::new(GAUGE_X, y + 1),
+        Size::new(bar_width as u32, LINE_HEIGHT as u32 - 3),
+    );
+    outline
+        .into_styled(PrimitiveStyle::with_stroke(BinaryColor::On, 1))
+        .draw(target)?;
+
+    // inside the outline
+    let span = f64::from(bar_width - 2);
+    let x_of =
+        |v: f64| GAUGE_X + 1 + ((v.clamp(min, max) - min) / (max - min) * span).round() as i32;
+    let origin = x_of(if min < 0.0 && max > 0.0 { 0.0 } else { min });
+    let end = x_of(value);
+    let (left, right) = (origin.min(end), origin.max(end));
+
+    if right > left {
+        Rectangle::new(
+            Point::new(left, y + 2),
+            Size::new((right - left) as u32, LINE_HEIGHT as u32 - 5),
+        )
+        .into_styled(PrimitiveStyle::with_fill(BinaryColor::On))
+        .draw(target)?;
+    }
+
+    Ok(())
+}
@@ -1,5 +1,15 @@
 use embedded_hal::digital::v2::OutputPin;
-use std::{thread::sleep, time::Duration};
+use std::convert::Infallible;
+use std::marker::PhantomData;
+use std::{
+    fmt,
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+pub mod homing;
+pub mod owned;
+pub mod planner;
 
 #[derive(Copy, Clone, PartialEq)]
 pub enum Dir {
@@ -7,62 +17,284 @@ pub enum Dir {
     CW,
 }
 
-pub struct StepperMotor<'a, PIN, E>
+impl Dir {
+    /// Returns the opposite direction
+    pub fn reverse(self) -> Self {
+        match self {
+            Dir::CCW => Dir::CW,
+            Dir::CW => Dir::CCW,
+        }
+    }
+}
+
+/// `SE` is the limit switch's error, only homing reads one
+#[derive(Debug)]
+pub enum Error<E, SE = Infallible> {
+    /// Error from the underlying pins
+    Pin(E),
+    /// Error reading the limit switch
+    Switch(SE),
+    /// The move would take the motor outside of its soft limits
+    SoftLimit,
+    /// Homing did not finish within the configured timeout
+    Timeout,
+    /// Homing travelled the maximum distance without finding the switch
+    MaxTravel,
+    /// The starting phase is not one of the pins
+    InvalidPhase(usize),
+    /// A multi-axis move did not have one entry per axis
+    AxisCount(usize),
+}
+
+impl<E: fmt::Debug, SE: fmt::Debug> fmt::Display for Error<E, SE> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Pin(e) => write!(f, "pin error: {:?}", e),
+            Error::Switch(e) => write!(f, "limit switch error: {:?}", e),
+            Error::SoftLimit => write!(f, "move exceeds soft limits"),
+            Error::Timeout => write!(f, "homing timed out"),
+            Error::MaxTravel => write!(f, "limit switch not found within maximum travel"),
+            Error::InvalidPhase(pos) => write!(f, "invalid starting phase: {}", pos),
+            Error::AxisCount(n) => write!(f, "expected one entry per axis, got {}", n),
+        }
+    }
+}
+
+impl<E: fmt::Debug, SE: fmt::Debug> std::error::Error for Error<E, SE> {}
+
+/// A stepper motor driven through the coil pins in `P`
+///
+/// `P` is anything that holds the pins as a slice. Use [`StepperMotor`] to
+/// borrow them, or [`OwnedStepperMotor`](owned::OwnedStepperMotor) to own
+/// them.
+pub struct Stepper<P, PIN, E>
 where
+    P: AsMut<[PIN]>,
     PIN: OutputPin<Error = E>,
 {
-    pins: &'a mut [PIN],
+    pins: P,
     pos: usize,
     dir: Dir,
     delay: Duration,
+    position: i64,
+    limits: Option<(i64, i64)>,
+    released: bool,
+    idle_timeout: Option<Duration>,
+    last_step: Instant,
+    _pins: PhantomData<fn() -> PIN>,
 }
 
+/// A stepper motor borrowing its coil pins
+pub type StepperMotor<'a, PIN, E> = Stepper<&'a mut [PIN], PIN, E>;
+
 impl<'a, PIN, E> StepperMotor<'a, PIN, E>
 where
     PIN: OutputPin<Error = E>,
 {
+    /// Creates a new `StepperMotor`, holding at phase `pos`
     pub fn new(
         pins: &'a mut [PIN],
         pos: usize,
         dir: Dir,
         delay: Duration,
-    ) -> Result<StepperMotor<'a, PIN, E>, E> {
-        for pin in pins.iter_mut() {
-            pin.set_low()?;
+    ) -> Result<StepperMotor<'a, PIN, E>, Error<E>> {
+        if pos >= pins.len() {
+            return Err(Error::InvalidPhase(pos));
         }
-        pins[pos].set_high()?;
-        sleep(delay);
 
-        Ok(Self {
+        Self::init(pins, pos, dir, delay).map_err(Error::Pin)
+    }
+}
+
+impl<P, PIN, E> Stepper<P, PIN, E>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+{
+    /// Holds at phase `pos`, which must be in range
+    fn init(pins: P, pos: usize, dir: Dir, delay: Duration) -> Result<Self, E> {
+        let mut motor = Self {
             pins,
             pos,
             dir,
             delay,
-        })
+            position: 0,
+            limits: None,
+            released: true,
+            idle_timeout: None,
+            last_step: Instant::now(),
+            _pins: PhantomData,
+        };
+        motor.release()?;
+        motor.hold()?;
+        sleep(delay);
+
+        Ok(motor)
     }
 
     pub fn set_dir(&mut self, dir: Dir) {
         self.dir = dir;
     }
 
+    pub fn set_delay(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+
+    /// Absolute position in steps, `CW` counts up
+    pub fn position(&self) -> i64 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: i64) {
+        self.position = position;
+    }
+
+    /// Sets the inclusive `(min, max)` range every later move must stay in
+    pub fn set_soft_limits(&mut self, limits: Option<(i64, i64)>) {
+        self.limits = limits;
+    }
+
+    /// Releases the coils automatically once idle for `timeout`
+    ///
+    /// The timeout is checked by [`poll`](Self::poll).
+    pub fn set_idle_timeout(&mut self, timeout: Option<Duration>) {
+        self.idle_timeout = timeout;
+    }
+
+    pub fn is_released(&self) -> bool {
+        self.released
+    }
+
+    /// De-energises every coil, the shaft is free to turn
+    ///
+    /// The next step re-energises them.
+    pub fn release(&mut self) -> Result<(), E> {
+        for pin in self.pins.as_mut() {
+            pin.set_low()?;
+        }
+        self.released = true;
+
+        Ok(())
+    }
+
+    /// Re-energises the current coil to hold position
+    pub fn hold(&mut self) -> Result<(), E> {
+        self.pins.as_mut()[self.pos].set_high()?;
+        self.released = false;
+        self.last_step = Instant::now();
+
+        Ok(())
+    }
+
+    /// Releases the coils if the idle timeout has passed
+    ///
+    /// Call this regularly from the control loop.
+    pub fn poll(&mut self) -> Result<(), E> {
+        match self.idle_timeout {
+            Some(timeout) if !self.released && self.last_step.elapsed() >= timeout => {
+                self.release()
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Takes one step, ignoring the soft limits
     pub fn step(&mut self) -> Result<(), E> {
+        self.step_unchecked()
+    }
+
+    /// Takes one step unless it would leave the soft limits
+    pub fn checked_step(&mut self) -> Result<(), Error<E>> {
+        let next = self.position + self.delta();
+        if !self.within_limits(next) {
+            return Err(Error::SoftLimit);
+        }
+
+        self.step_unchecked().map_err(Error::Pin)
+    }
+
+    /// Moves `steps` steps, negative is `CCW`
+    ///
+    /// The target is checked against the soft limits before moving.
+    pub fn move_by(&mut self, steps: i64) -> Result<(), Error<E>> {
+        self.move_to(self.position + steps)
+    }
+
+    /// Moves to an absolute position
+    ///
+    /// The target is checked against the soft limits before moving.
+    pub fn move_to(&mut self, target: i64) -> Result<(), Error<E>> {
+        if !self.within_limits(target) {
+            return Err(Error::SoftLimit);
+        }
+
+        self.dir = if target > self.position {
+            Dir::CW
+        } else {
+            Dir::CCW
+        };
+
+        while self.position != target {
+            self.step_unchecked().map_err(Error::Pin)?;
+        }
+
+        Ok(())
+    }
+
+    /// Releases the coils and returns the pins
+    pub fn into_pins(mut self) -> Result<P, E> {
+        self.release()?;
+        Ok(self.pins)
+    }
+
+    fn step_unchecked(&mut self) -> Result<(), E> {
+        self.advance()?;
+        sleep(self.delay);
+
+        Ok(())
+    }
+
+    /// Energises the next coil without waiting, re-energising the coils
+    /// first if released
+    fn advance(&mut self) -> Result<(), E> {
+        if self.released {
+            self.hold()?;
+        }
+
         // update state
         let curr = self.pos;
         let next = self.next_pos();
 
-        self.pins[curr].set_low()?;
-        self.pins[next].set_high()?;
+        let pins = self.pins.as_mut();
+        pins[curr].set_low()?;
+        pins[next].set_high()?;
         self.pos = next;
-
-        sleep(self.delay);
+        self.position += self.delta();
+        self.last_step = Instant::now();
 
         Ok(())
     }
 
+    fn delta(&self) -> i64 {
+        match self.dir {
+            Dir::CW => 1,
+            Dir::CCW => -1,
+        }
+    }
+
+    fn within_limits(&self, position: i64) -> bool {
+        match self.limits {
+            Some((min, max)) => position >= min && position <= max,
+            None => true,
+        }
+    }
+
     fn next_pos(&mut self) -> usize {
+        let len = self.pins.as_mut().len();
+
         match self.dir {
             Dir::CW => {
-                if self.pos == self.pins.len() - 1 {
+                if self.pos == len - 1 {
                     0
                 } else {
                     self.pos + 1
@@ -70,7 +302,7 @@ where
             }
             Dir::CCW => {
                 if self.pos == 0 {
-                    self.pins.len() - 1
+                    len - 1
                 } else {
                     self.pos - 1
                 }
@@ -0,0 +1,139 @@
+//! Limit switch homing
+
+use super::{Dir, Error, Stepper};
+use embedded_hal::digital::v2::OutputPin;
+use std::time::{Duration, Instant};
+use switch_hal::InputSwitch;
+
+/// Homing parameters
+///
+/// The motor seeks toward the switch at `seek_delay` per step, backs off
+/// `backoff` steps, then re-approaches at `approach_delay` per step. The
+/// position where the switch triggers the second time becomes zero.
+pub struct Homing {
+    /// Direction toward the limit switch
+    pub dir: Dir,
+    pub seek_delay: Duration,
+    pub approach_delay: Duration,
+    pub backoff: u32,
+    /// Maximum steps allowed in each phase before giving up
+    pub max_travel: u32,
+    pub timeout: Duration,
+}
+
+impl Homing {
+    pub fn new(dir: Dir) -> Self {
+        Self {
+            dir,
+            seek_delay: Duration::from_millis(3),
+            approach_delay: Duration::from_millis(20),
+            backoff: 64,
+            max_travel: 4096,
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+impl<P, PIN, E> Stepper<P, PIN, E>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+{
+    /// Drives toward the limit switch and sets the position to zero
+    ///
+    /// The switch is active when pressed, e.g. `pin.into_active_low_switch()`
+    /// for a normally open switch to ground with a pull-up.
+    /// It can be on another HAL or an expander, its errors come back as
+    /// [`Error::Switch`].
+    ///
+    /// Soft limits are ignored while homing. Direction and delay are
+    /// restored afterward.
+    pub fn home<SW, SE>(&mut self, switch: &SW, homing: &Homing) -> Result<(), Error<E, SE>>
+    where
+        SW: InputSwitch<Error = SE>,
+    {
+        let dir = self.dir;
+        let delay = self.delay;
+        let started = Instant::now();
+
+        let result = self.run_homing(switch, homing, started);
+
+        self.dir = dir;
+        self.delay = delay;
+
+        result
+    }
+
+    fn run_homing<SW, SE>(
+        &mut self,
+        switch: &SW,
+        homing: &Homing,
+        started: Instant,
+    ) -> Result<(), Error<E, SE>>
+    where
+        SW: InputSwitch<Error = SE>,
+    {
+        let away = homing.dir.reverse();
+
+        // already sitting on the switch, get off it first
+        self.dir = away;
+        self.delay = homing.seek_delay;
+        self.step_while(switch, homing, started, true)?;
+
+        // fast seek
+        self.dir = homing.dir;
+        self.step_while(switch, homing, started, false)?;
+
+        // back off
+        self.dir = away;
+        self.step_while(switch, homing, started, true)?;
+        for _ in 0..homing.backoff {
+            self.step_checked_timeout(homing, started)?;
+        }
+
+        // slow approach
+        self.dir = homing.dir;
+        self.delay = homing.approach_delay;
+        self.step_while(switch, homing, started, false)?;
+
+        self.position = 0;
+
+        Ok(())
+    }
+
+    /// Steps until the switch state is no longer `pressed`
+    fn step_while<SW, SE>(
+        &mut self,
+        switch: &SW,
+        homing: &Homing,
+        started: Instant,
+        pressed: bool,
+    ) -> Result<(), Error<E, SE>>
+    where
+        SW: InputSwitch<Error = SE>,
+    {
+        let mut steps = 0;
+
+        while switch.is_active().map_err(Error::Switch)? == pressed {
+            if steps >= homing.max_travel {
+                return Err(Error::MaxTravel);
+            }
+            self.step_checked_timeout(homing, started)?;
+            steps += 1;
+        }
+
+        Ok(())
+    }
+
+    fn step_checked_timeout<SE>(
+        &mut self,
+        homing: &Homing,
+        started: Instant,
+    ) -> Result<(), Error<E, SE>> {
+        if started.elapsed() > homing.timeout {
+            return Err(Error::Timeout);
+        }
+
+        self.step_unchecked().map_err(Error::Pin)
+    }
+}
@@ -0,0 +1,26 @@
+//! Stepper motor that owns its coil pins
+
+use super::{Dir, Error, Stepper};
+use embedded_hal::digital::v2::OutputPin;
+use std::time::Duration;
+
+/// A stepper motor with `N` phases that owns its pins
+///
+/// Unlike [`super::StepperMotor`] this can be stored in long-lived structs
+/// and moved between threads. Both share [`Stepper`], so soft limits,
+/// homing and the [`Planner`](super::planner::Planner) work the same.
+pub type OwnedStepperMotor<PIN, E, const N: usize> = Stepper<[PIN; N], PIN, E>;
+
+impl<PIN, E, const N: usize> OwnedStepperMotor<PIN, E, N>
+where
+    PIN: OutputPin<Error = E>,
+{
+    /// Creates a new `OwnedStepperMotor`, holding at phase `pos`
+    pub fn new(pins: [PIN; N], pos: usize, dir: Dir, delay: Duration) -> Result<Self, Error<E>> {
+        if pos >= N {
+            return Err(Error::InvalidPhase(pos));
+        }
+
+        Self::init(pins, pos, dir, delay).map_err(Error::Pin)
+    }
+}
@@ -0,0 +1,120 @@
+//! Coordinated moves across several steppers
+
+use super::{Dir, Error, Stepper};
+use embedded_hal::digital::v2::OutputPin;
+use std::{thread::sleep, time::Duration};
+
+/// Drives several steppers so they start and finish a move together
+///
+/// Steps are distributed with Bresenham's line algorithm: the axis with the
+/// longest travel steps on every tick and the others step whenever their
+/// accumulated error overflows.
+///
+/// The motors can borrow or own their pins, see [`Stepper`].
+pub struct Planner<P, PIN, E>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+{
+    motors: Vec<Stepper<P, PIN, E>>,
+}
+
+impl<P, PIN, E> Planner<P, PIN, E>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+{
+    pub fn new(motors: Vec<Stepper<P, PIN, E>>) -> Self {
+        Self { motors }
+    }
+
+    /// Number of axes
+    pub fn axes(&self) -> usize {
+        self.motors.len()
+    }
+
+    pub fn positions(&self) -> Vec<i64> {
+        self.motors.iter().map(|m| m.position()).collect()
+    }
+
+    pub fn motors_mut(&mut self) -> &mut [Stepper<P, PIN, E>] {
+        &mut self.motors
+    }
+
+    /// The shortest tick every motor can keep up with
+    pub fn min_delay(&self) -> Duration {
+        self.motors
+            .iter()
+            .map(|m| m.delay)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Moves every motor to its absolute target
+    ///
+    /// `delay` is the time per step of the longest axis and is never shorter
+    /// than [`Planner::min_delay`]. All targets are checked against the soft
+    /// limits before anything moves, and `targets` must have one entry per
+    /// axis.
+    pub fn move_to(&mut self, targets: &[i64], delay: Duration) -> Result<(), Error<E>> {
+        if targets.len() != self.motors.len() {
+            return Err(Error::AxisCount(targets.len()));
+        }
+
+        if self
+            .motors
+            .iter()
+            .zip(targets)
+            .any(|(m, t)| !m.within_limits(*t))
+        {
+            return Err(Error::SoftLimit);
+        }
+
+        let deltas: Vec<i64> = self
+            .motors
+            .iter()
+            .zip(targets)
+            .map(|(m, t)| t - m.position())
+            .collect();
+        let major = deltas.iter().map(|d| d.abs()).max().unwrap_or(0);
+        if major == 0 {
+            return Ok(());
+        }
+
+        for (m, d) in self.motors.iter_mut().zip(&deltas) {
+            m.set_dir(if *d > 0 { Dir::CW } else { Dir::CCW });
+        }
+
+        let delay = delay.max(self.min_delay());
+        let mut errors = vec![major / 2; deltas.len()];
+
+        for _ in 0..major {
+            for ((m, d), err) in self.motors.iter_mut().zip(&deltas).zip(errors.iter_mut()) {
+                *err += d.abs();
+                if *err >= major {
+                    *err -= major;
+                    m.advance().map_err(Error::Pin)?;
+                }
+            }
+            sleep(delay);
+        }
+
+        Ok(())
+    }
+
+    /// Moves every motor relative to its current position
+    pub fn move_by(&mut self, steps: &[i64], delay: Duration) -> Result<(), Error<E>> {
+        if steps.len() != self.motors.len() {
+            return Err(Error::AxisCount(steps.len()));
+        }
+
+        let targets: Vec<i64> = self
+            .motors
+            .iter()
+            .zip(steps)
+            .map(|(m, s)| m.position() + s)
+            .collect();
+
+        self.move_to(&targets, delay)
+    }
+}
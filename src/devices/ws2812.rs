@@ -0,0 +1,169 @@
+//! WS2812B / SK6812 addressable LEDs over SPI
+//!
+//! Each data bit is sent as four SPI bits, `1000` for 0 and `1100` for 1, so
+//! the SPI clock must be 3 MHz (e.g. rppal's `Spi` at `3_000_000`). Only
+//! MOSI is connected to the strip's DIN.
+
+use super::rgb_led::Rgb;
+use embedded_hal::blocking::spi::Write;
+
+/// SPI clock the encoding is timed for
+pub const SPI_FREQUENCY: u32 = 3_000_000;
+
+/// Zero bytes after a frame, 300us at 3 MHz latches the data
+const RESET_BYTES: usize = 113;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ColorOrder {
+    /// WS2811 and some clones
+    Rgb,
+    /// WS2812B, SK6812 RGB
+    Grb,
+    /// SK6812 RGBW
+    Grbw,
+    Rgbw,
+}
+
+impl ColorOrder {
+    /// Bytes per pixel
+    pub fn channels(self) -> usize {
+        match self {
+            ColorOrder::Rgb | ColorOrder::Grb => 3,
+            ColorOrder::Grbw | ColorOrder::Rgbw => 4,
+        }
+    }
+}
+
+/// A strip of addressable LEDs with a frame buffer
+///
+/// Draw into the buffer with [`set_pixel`](Self::set_pixel) or
+/// [`pixels_mut`](Self::pixels_mut), then send it with
+/// [`show`](Self::show).
+pub struct WS2812<SPI> {
+    spi: SPI,
+    order: ColorOrder,
+    brightness: f64,
+    pixels: Vec<Rgb>,
+    buf: Vec<u8>,
+}
+
+impl<SPI, E> WS2812<SPI>
+where
+    SPI: Write<u8, Error = E>,
+{
+    /// Creates a new `WS2812` with `len` pixels, all off
+    pub fn new(spi: SPI, len: usize, order: ColorOrder) -> Self {
+        Self {
+            spi,
+            order,
+            brightness: 1.0,
+            pixels: vec![Rgb::BLACK; len],
+            buf: Vec::with_capacity(len * order.channels() * 4 + RESET_BYTES),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pixels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pixels.is_empty()
+    }
+
+    /// Scales every pixel on output, `0.0..=1.0`
+    pub fn set_brightness(&mut self, brightness: f64) {
+        self.brightness = brightness.clamp(0.0, 1.0);
+    }
+
+    pub fn brightness(&self) -> f64 {
+        self.brightness
+    }
+
+    /// Sets pixel `index`, out of range indices are ignored
+    pub fn set_pixel(&mut self, index: usize, color: Rgb) {
+        if let Some(pixel) = self.pixels.get_mut(index) {
+            *pixel = color;
+        }
+    }
+
+    pub fn pixels(&self) -> &[Rgb] {
+        &self.pixels
+    }
+
+    pub fn pixels_mut(&mut self) -> &mut [Rgb] {
+        &mut self.pixels
+    }
+
+    pub fn fill(&mut self, color: Rgb) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.fill(Rgb::BLACK);
+    }
+
+    /// Sends the frame buffer to the strip
+    ///
+    /// For RGBW strips the common part of red, green and blue is moved to
+    /// the white LED.
+    pub fn show(&mut self) -> Result<(), E> {
+        self.buf.clear();
+
+        for pixel in self.pixels.iter() {
+            let c = pixel.scale(self.brightness);
+            let (r, g, b) = (to_u8(c.r), to_u8(c.g), to_u8(c.b));
+
+            let bytes: &[u8] = match self.order {
+                ColorOrder::Rgb => &[r, g, b],
+                ColorOrder::Grb => &[g, r, b],
+                ColorOrder::Grbw | ColorOrder::Rgbw => {
+                    let w = r.min(g).min(b);
+                    let (r, g, b) = (r - w, g - w, b - w);
+                    if self.order == ColorOrder::Grbw {
+                        &[g, r, b, w]
+                    } else {
+                        &[r, g, b, w]
+                    }
+                }
+            };
+
+            for byte in bytes {
+                encode(*byte, &mut self.buf);
+            }
+        }
+
+        self.buf.resize(self.buf.len() + RESET_BYTES, 0);
+        self.spi.write(&self.buf)
+    }
+
+    /// Turns every LED off now
+    pub fn off(&mut self) -> Result<(), E> {
+        self.clear();
+        self.show()
+    }
+
+    pub fn release(self) -> SPI {
+        self.spi
+    }
+}
+
+fn to_u8(v: f64) -> u8 {
+    (v.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Encodes one byte, MSB first, two data bits per SPI byte
+fn encode(byte: u8, buf: &mut Vec<u8>) {
+    let symbol = |bit: u8| {
+        if byte & (1 << bit) != 0 {
+            0b1100
+        } else {
+            0b1000
+        }
+    };
+
+    for i in (0..8).step_by(2).rev() {
+        buf.push(symbol(i + 1) << 4 | symbol(i));
+    }
+}
@@ -0,0 +1,290 @@
+//! Minimal G-code subset
+//!
+//! | Code | Meaning                                   |
+//! |------|-------------------------------------------|
+//! | G0   | rapid move                                |
+//! | G1   | linear move at the feed rate `F` (mm/min) |
+//! | G28  | return the given axes (or all) to zero    |
+//! | G90  | absolute positioning                      |
+//! | G91  | relative positioning                      |
+//! | M3   | tool on (e.g. pen down)                   |
+//! | M5   | tool off (e.g. pen up)                    |
+//!
+//! Comments (`;` to end of line and `( ... )`) and line numbers (`N`) are
+//! ignored.
+
+use std::fmt;
+
+pub mod machine;
+
+/// Axis letters, in axis order
+pub const AXES: [char; 3] = ['X', 'Y', 'Z'];
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Target {
+    pub axes: [Option<f64>; AXES.len()],
+    pub feed: Option<f64>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Command {
+    Rapid(Target),
+    Linear(Target),
+    Home(Target),
+    Absolute,
+    Relative,
+    ToolOn,
+    ToolOff,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// A word that is not a letter followed by a number
+    InvalidWord(String),
+    /// A valid word this interpreter does not implement
+    Unsupported(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::InvalidWord(w) => write!(f, "invalid word: {}", w),
+            ParseError::Unsupported(w) => write!(f, "unsupported word: {}", w),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Copy, Clone, PartialEq)]
+enum Motion {
+    Rapid,
+    Linear,
+}
+
+/// Line parser
+///
+/// Keeps the modal motion so lines with only coordinates continue the last
+/// `G0`/`G1`.
+pub struct Parser {
+    motion: Motion,
+}
+
+impl Default for Parser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self {
+            motion: Motion::Rapid,
+        }
+    }
+
+    /// Parses one line into commands, in execution order
+    pub fn parse_line(&mut self, line: &str) -> Result<Vec<Command>, ParseError> {
+        let mut modes = Vec::new();
+        let mut motion = None;
+        let mut home = false;
+        let mut target = Target::default();
+
+        for word in words(&strip_comments(line)) {
+            let (letter, value) = split_word(&word)?;
+
+            // G and M codes are whole numbers, anything else is unsupported
+            let code = if value.fract() == 0.0 {
+                value as i64
+            } else {
+                -1
+            };
+
+            match (letter, code) {
+                ('G', 0) => motion = Some(Motion::Rapid),
+                ('G', 1) => motion = Some(Motion::Linear),
+                ('G', 28) => home = true,
+                ('G', 90) => modes.push(Command::Absolute),
+                ('G', 91) => modes.push(Command::Relative),
+                ('M', 3) => modes.push(Command::ToolOn),
+                ('M', 5) => modes.push(Command::ToolOff),
+                ('F', _) if value > 0.0 => target.feed = Some(value),
+                ('F', _) => return Err(ParseError::InvalidWord(word)),
+                ('N', _) => {}
+                (l, _) => match AXES.iter().position(|a| *a == l) {
+                    Some(i) => target.axes[i] = Some(value),
+                    None => return Err(ParseError::Unsupported(word)),
+                },
+            }
+        }
+
+        let mut commands = modes;
+
+        if home {
+            commands.push(Command::Home(target));
+        } else {
+            if let Some(m) = motion {
+                self.motion = m;
+            }
+            if motion.is_some() || target != Target::default() {
+                commands.push(match self.motion {
+                    Motion::Rapid => Command::Rapid(target),
+                    Motion::Linear => Command::Linear(target),
+                });
+            }
+        }
+
+        Ok(commands)
+    }
+}
+
+fn strip_comments(line: &str) -> String {
+    let line = line.split(';').next().unwrap_or("");
+    let mut out = String::with_capacity(line.len());
+    let mut depth = 0;
+
+    for c in line.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => out.push(c),
+            _ => {}
+        }
+    }
+
+    out
+}
+
+/// Splits a line into words, each starting at a letter
+fn words(line: &str) -> Vec<String> {
+    let mut words: Vec<String> = Vec::new();
+
+    for c in line.chars().filter(|c| !c.is_whitespace()) {
+        let c = c.to_ascii_uppercase();
+        match words.last_mut() {
+            Some(w) if !c.is_ascii_alphabetic() => w.push(c),
+            _ => words.push(c.to_string()),
+        }
+    }
+
+    words
+}
+
+fn split_word(word: &str) -> Result<(char, f64), ParseError> {
+    let mut chars = word.chars();
+    let letter = chars
+        .next()
+        .filter(|c| c.is_ascii_alphabetic())
+        .ok_or_else(|| ParseError::InvalidWord(word.to_string()))?;
+    let value = chars
+        .as_str()
+        .parse()
+        .ok()
+        .filter(|v: &f64| v.is_finite())
+        .ok_or_else(|| ParseError::InvalidWord(word.to_string()))?;
+
+    Ok((letter, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xy(x: f64, y: f64) -> Target {
+        Target {
+            axes: [Some(x), Some(y), None],
+            feed: None,
+        }
+    }
+
+    #[test]
+    fn motion_is_modal() {
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parser.parse_line("X1 Y2"),
+            Ok(vec![Command::Rapid(xy(1.0, 2.0))])
+        );
+        assert_eq!(
+            parser.parse_line("G1 X1 Y2"),
+            Ok(vec![Command::Linear(xy(1.0, 2.0))])
+        );
+        assert_eq!(
+            parser.parse_line("X3 Y4"),
+            Ok(vec![Command::Linear(xy(3.0, 4.0))])
+        );
+        assert_eq!(
+            parser.parse_line("G0"),
+            Ok(vec![Command::Rapid(Target::default())])
+        );
+        assert_eq!(
+            parser.parse_line("X5 Y6"),
+            Ok(vec![Command::Rapid(xy(5.0, 6.0))])
+        );
+    }
+
+    #[test]
+    fn feed_without_motion() {
+        let mut parser = Parser::new();
+        let target = Target {
+            feed: Some(300.0),
+            ..Target::default()
+        };
+
+        assert_eq!(parser.parse_line("F300"), Ok(vec![Command::Rapid(target)]));
+    }
+
+    #[test]
+    fn positioning_modes() {
+        let mut parser = Parser::new();
+
+        assert_eq!(parser.parse_line("G90"), Ok(vec![Command::Absolute]));
+        assert_eq!(
+            parser.parse_line("G91 G1 X1 Y-1"),
+            Ok(vec![Command::Relative, Command::Linear(xy(1.0, -1.0))])
+        );
+    }
+
+    #[test]
+    fn comments_and_line_numbers() {
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parser.parse_line("N10 G1 (move) X1 Y2 ; done"),
+            Ok(vec![Command::Linear(xy(1.0, 2.0))])
+        );
+        assert_eq!(parser.parse_line("; nothing"), Ok(vec![]));
+    }
+
+    #[test]
+    fn bad_words() {
+        let mut parser = Parser::new();
+
+        assert_eq!(
+            parser.parse_line("G1 X1.2.3"),
+            Err(ParseError::InvalidWord("X1.2.3".to_string()))
+        );
+        assert_eq!(
+            parser.parse_line("G1 X"),
+            Err(ParseError::InvalidWord("X".to_string()))
+        );
+        let huge = format!("X1{}", "0".repeat(400));
+        assert_eq!(parser.parse_line(&huge), Err(ParseError::InvalidWord(huge)));
+        assert_eq!(
+            parser.parse_line("G1 F0"),
+            Err(ParseError::InvalidWord("F0".to_string()))
+        );
+        assert_eq!(
+            parser.parse_line("G2 X1"),
+            Err(ParseError::Unsupported("G2".to_string()))
+        );
+        assert_eq!(
+            parser.parse_line("G1.5"),
+            Err(ParseError::Unsupported("G1.5".to_string()))
+        );
+        assert_eq!(
+            parser.parse_line("A1"),
+            Err(ParseError::Unsupported("A1".to_string()))
+        );
+    }
+}
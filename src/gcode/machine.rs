@@ -0,0 +1,302 @@
+//! Runs G-code on a stepper [`Planner`] and a tool switch
+
+use super::{Command, ParseError, Parser, Target, AXES};
+use crate::devices::stepper_motor::{self, planner::Planner};
+use embedded_hal::digital::v2::OutputPin;
+use std::{fmt, time::Duration};
+use switch_hal::OutputSwitch;
+
+#[derive(Debug)]
+pub enum Error<E, T> {
+    Parse(ParseError),
+    Motion(stepper_motor::Error<E>),
+    Tool(T),
+    /// An axis word for an axis the machine doesn't have
+    Axis(char),
+    /// The feed rate is too low to turn into a step delay
+    Feed(f64),
+}
+
+impl<E: fmt::Debug, T: fmt::Debug> fmt::Display for Error<E, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{}", e),
+            Error::Motion(e) => write!(f, "{}", e),
+            Error::Tool(e) => write!(f, "tool error: {:?}", e),
+            Error::Axis(a) => write!(f, "no {} axis on this machine", a),
+            Error::Feed(rate) => write!(f, "feed rate too low: {}", rate),
+        }
+    }
+}
+
+impl<E: fmt::Debug, T: fmt::Debug> std::error::Error for Error<E, T> {}
+
+/// A G-code machine
+///
+/// Axis `i` of the planner is driven by the `i`-th letter of [`AXES`]. The
+/// tool (`M3`/`M5`) is any [`OutputSwitch`], such as a servo pen lift.
+pub struct Machine<P, PIN, E, T>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+    T: OutputSwitch,
+{
+    parser: Parser,
+    planner: Planner<P, PIN, E>,
+    tool: T,
+    steps_per_mm: Vec<f64>,
+    position: Vec<f64>,
+    absolute: bool,
+    feed: f64,
+    rapid: f64,
+}
+
+impl<P, PIN, E, T> Machine<P, PIN, E, T>
+where
+    P: AsMut<[PIN]>,
+    PIN: OutputPin<Error = E>,
+    T: OutputSwitch,
+{
+    /// Creates a new `Machine`
+    ///
+    /// `feed` is the initial `G1` feed rate and `rapid` the `G0` rate, both
+    /// in mm/min.
+    ///
+    /// # Panics
+    ///
+    /// If `steps_per_mm` does not have one entry per planner axis, or there
+    /// are more axes than [`AXES`].
+    pub fn new(
+        planner: Planner<P, PIN, E>,
+        tool: T,
+        steps_per_mm: &[f64],
+        feed: f64,
+        rapid: f64,
+    ) -> Self {
+        assert_eq!(steps_per_mm.len(), planner.axes(), "one scale per axis");
+        assert!(planner.axes() <= AXES.len(), "too many axes");
+
+        let position = planner
+            .positions()
+            .iter()
+            .zip(steps_per_mm)
+            .map(|(p, s)| *p as f64 / s)
+            .collect();
+
+        Self {
+            parser: Parser::new(),
+            planner,
+            tool,
+            steps_per_mm: steps_per_mm.to_vec(),
+            position,
+            absolute: true,
+            feed,
+            rapid,
+        }
+    }
+
+    /// Current position in mm
+    pub fn position(&self) -> &[f64] {
+        &self.position
+    }
+
+    /// Current `G1` feed rate in mm/min
+    pub fn feed(&self) -> f64 {
+        self.feed
+    }
+
+    pub fn tool_mut(&mut self) -> &mut T {
+        &mut self.tool
+    }
+
+    pub fn execute_line(&mut self, line: &str) -> Result<(), Error<E, T::Error>> {
+        let commands = self.parser.parse_line(line).map_err(Error::Parse)?;
+
+        for command in commands {
+            self.execute(command)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn execute(&mut self, command: Command) -> Result<(), Error<E, T::Error>> {
+        if let Command::Rapid(target) | Command::Linear(target) | Command::Home(target) = command {
+            self.check_axes(&target)?;
+            // the feed rate is modal, whatever the motion mode
+            if let Some(feed) = target.feed {
+                self.feed = feed;
+            }
+        }
+
+        match command {
+            Command::Rapid(target) => self.linear(target, self.rapid),
+            Command::Linear(target) => self.linear(target, self.feed),
+            Command::Home(target) => {
+                let all = target.axes.iter().all(|a| a.is_none());
+                let position = self
+                    .position
+                    .iter()
+                    .zip(&target.axes)
+                    .map(|(p, a)| if all || a.is_some() { 0.0 } else { *p })
+                    .collect();
+                self.move_to(position, self.rapid)
+            }
+            Command::Absolute => {
+                self.absolute = true;
+                Ok(())
+            }
+            Command::Relative => {
+                self.absolute = false;
+                Ok(())
+            }
+            Command::ToolOn => self.tool.on().map_err(Error::Tool),
+            Command::ToolOff => self.tool.off().map_err(Error::Tool),
+        }
+    }
+
+    fn check_axes(&self, target: &Target) -> Result<(), Error<E, T::Error>> {
+        match target.axes[self.position.len()..]
+            .iter()
+            .position(|a| a.is_some())
+        {
+            Some(i) => Err(Error::Axis(AXES[self.position.len() + i])),
+            None => Ok(()),
+        }
+    }
+
+    fn linear(&mut self, target: Target, rate: f64) -> Result<(), Error<E, T::Error>> {
+        let position = self
+            .position
+            .iter()
+            .zip(&target.axes)
+            .map(|(p, a)| match a {
+                Some(v) if self.absolute => *v,
+                Some(v) => p + v,
+                None => *p,
+            })
+            .collect();
+
+        self.move_to(position, rate)
+    }
+
+    fn move_to(&mut self, position: Vec<f64>, rate: f64) -> Result<(), Error<E, T::Error>> {
+        let distance = self
+            .position
+            .iter()
+            .zip(&position)
+            .map(|(a, b)| (b - a).powi(2))
+            .sum::<f64>()
+            .sqrt();
+
+        let targets: Vec<i64> = position
+            .iter()
+            .zip(&self.steps_per_mm)
+            .map(|(p, s)| (p * s).round() as i64)
+            .collect();
+        let major = self
+            .planner
+            .positions()
+            .iter()
+            .zip(&targets)
+            .map(|(a, b)| (b - a).abs())
+            .max()
+            .unwrap_or(0);
+
+        let delay = if major > 0 && rate > 0.0 {
+            Duration::try_from_secs_f64(distance / rate * 60.0 / major as f64)
+                .map_err(|_| Error::Feed(rate))?
+        } else {
+            Duration::default()
+        };
+
+        self.planner
+            .move_to(&targets, delay)
+            .map_err(Error::Motion)?;
+        self.position = position;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devices::stepper_motor::{Dir, StepperMotor};
+    use std::convert::Infallible;
+
+    struct Pin;
+
+    impl OutputPin for Pin {
+        type Error = Infallible;
+
+        fn set_low(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn set_high(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    struct Tool;
+
+    impl OutputSwitch for Tool {
+        type Error = Infallible;
+
+        fn on(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+
+        fn off(&mut self) -> Result<(), Infallible> {
+            Ok(())
+        }
+    }
+
+    fn run(lines: &[&str]) -> (Result<(), Error<Infallible, Infallible>>, f64) {
+        let mut x = [Pin, Pin, Pin, Pin];
+        let mut y = [Pin, Pin, Pin, Pin];
+        let x = StepperMotor::new(&mut x, 0, Dir::CW, Duration::ZERO).unwrap();
+        let y = StepperMotor::new(&mut y, 0, Dir::CW, Duration::ZERO).unwrap();
+        let mut machine = Machine::new(Planner::new(vec![x, y]), Tool, &[1.0, 1.0], 100.0, 1e9);
+
+        let result = lines.iter().try_for_each(|l| machine.execute_line(l));
+        (result, machine.feed())
+    }
+
+    #[test]
+    fn feed_is_modal_in_rapid_mode() {
+        assert_eq!(run(&["G0 F250"]).1, 250.0);
+        assert_eq!(run(&["G0", "F300"]).1, 300.0);
+        assert_eq!(run(&["G1 F200", "G0 F400", "G1"]).1, 400.0);
+    }
+
+    #[test]
+    fn rejects_missing_axis() {
+        assert!(matches!(run(&["G1 Z1"]).0, Err(Error::Axis('Z'))));
+        assert!(matches!(run(&["G28 Z0"]).0, Err(Error::Axis('Z'))));
+        assert!(run(&["G28 X0"]).0.is_ok());
+    }
+
+    #[test]
+    fn rejects_unusable_feed() {
+        assert!(matches!(
+            run(&["G1 X1 F0.000000000000000001"]).0,
+            Err(Error::Feed(_))
+        ));
+        assert!(matches!(run(&["G1 X1 F0"]).0, Err(Error::Parse(_))));
+        assert!(matches!(run(&["G1 X1 F-5"]).0, Err(Error::Parse(_))));
+        assert!(run(&["G1 X1 F6000"]).0.is_ok());
+    }
+
+    #[test]
+    fn planner_checks_axis_count() {
+        let mut x = [Pin, Pin, Pin, Pin];
+        let x = StepperMotor::new(&mut x, 0, Dir::CW, Duration::ZERO).unwrap();
+        let mut planner = Planner::new(vec![x]);
+
+        let short = planner.move_by(&[], Duration::ZERO);
+        assert!(matches!(short, Err(stepper_motor::Error::AxisCount(0))));
+        let long = planner.move_to(&[1, 2], Duration::ZERO);
+        assert!(matches!(long, Err(stepper_motor::Error::AxisCount(2))));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod animation;
 pub mod devices;
+pub mod gcode;
 pub mod utils;
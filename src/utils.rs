@@ -1,3 +1,9 @@
+pub mod edges;
+pub mod soft_pwm;
+pub mod units;
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
 pub fn convert_nb_error<E>(r: Result<u8, nb::Error<E>>) -> Result<Option<u8>, E> {
     match r {
         Ok(v) => Ok(Some(v)),
@@ -7,3 +13,55 @@ pub fn convert_nb_error<E>(r: Result<u8, nb::Error<E>>) -> Result<Option<u8>, E>
         },
     }
 }
+
+/// Locks `mutex`, carrying on if another thread panicked while holding it
+///
+/// Only for data that stays valid whatever a panic left behind, such as a
+/// shadow of pin states or the latest sensor reading.
+pub fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Scales a PWM duty value, so devices can work with any `PwmPin::Duty`
+pub trait Duty: Copy {
+    /// Returns `ratio` (0.0..=1.0) of `max`
+    fn from_ratio(max: Self, ratio: f64) -> Self;
+
+    /// Returns the fraction of `max` this duty represents
+    fn ratio(self, max: Self) -> f64;
+}
+
+macro_rules! impl_duty_float {
+    ($($t:ty),*) => {
+        $(
+            impl Duty for $t {
+                fn from_ratio(max: Self, ratio: f64) -> Self {
+                    max * ratio.clamp(0.0, 1.0) as $t
+                }
+
+                fn ratio(self, max: Self) -> f64 {
+                    (self / max) as f64
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_duty_int {
+    ($($t:ty),*) => {
+        $(
+            impl Duty for $t {
+                fn from_ratio(max: Self, ratio: f64) -> Self {
+                    (max as f64 * ratio.clamp(0.0, 1.0)).round() as $t
+                }
+
+                fn ratio(self, max: Self) -> f64 {
+                    self as f64 / max as f64
+                }
+            }
+        )*
+    };
+}
+
+impl_duty_float!(f32, f64);
+impl_duty_int!(u8, u16, u32);
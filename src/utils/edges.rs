@@ -0,0 +1,42 @@
+//! Timestamped edges from rppal's GPIO interrupts
+
+use rppal::gpio::{self, InputPin, Level, Trigger};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, TryIter};
+use std::time::{Duration, Instant};
+
+/// Timestamps both edges of an input from an interrupt callback
+///
+/// The timestamps are taken on rppal's interrupt thread, so they don't
+/// depend on how often the edges are read.
+pub struct Edges {
+    pin: InputPin,
+    receiver: Receiver<(Level, Instant)>,
+}
+
+impl Edges {
+    pub fn new(mut pin: InputPin) -> gpio::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+
+        pin.set_async_interrupt(Trigger::Both, move |level| {
+            // the receiver is only gone while the pin is being released
+            let _ = sender.send((level, Instant::now()));
+        })?;
+
+        Ok(Self { pin, receiver })
+    }
+
+    /// The new level and when it changed, for each edge so far
+    pub fn try_iter(&self) -> TryIter<'_, (Level, Instant)> {
+        self.receiver.try_iter()
+    }
+
+    /// Waits up to `timeout` for the next edge
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<(Level, Instant), RecvTimeoutError> {
+        self.receiver.recv_timeout(timeout)
+    }
+
+    pub fn release(mut self) -> gpio::Result<InputPin> {
+        self.pin.clear_async_interrupt()?;
+        Ok(self.pin)
+    }
+}
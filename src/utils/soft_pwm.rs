@@ -0,0 +1,108 @@
+//! rppal software PWM as an embedded-hal `PwmPin`
+
+use embedded_hal::PwmPin;
+use rppal::gpio::OutputPin;
+use rppal::pwm::Polarity;
+
+/// Software PWM on any GPIO pin
+///
+/// Duty is `0.0..=1.0`. `PwmPin` cannot report errors, so rppal errors are
+/// dropped. Like the hardware channels, [`Polarity::Inverse`] makes the
+/// duty the low time and keeps the pin high while disabled, e.g. for
+/// common anode LEDs.
+pub struct SoftPwm {
+    pin: OutputPin,
+    frequency: f64,
+    duty: f64,
+    polarity: Polarity,
+    enabled: bool,
+}
+
+impl SoftPwm {
+    /// Creates a new `SoftPwm`, disabled
+    pub fn new(pin: OutputPin, frequency: f64) -> Self {
+        Self::with_polarity(pin, frequency, Polarity::Normal)
+    }
+
+    /// Creates a new `SoftPwm`, disabled and at its inactive level
+    pub fn with_polarity(pin: OutputPin, frequency: f64, polarity: Polarity) -> Self {
+        let mut pwm = Self {
+            pin,
+            frequency,
+            duty: 0.0,
+            polarity,
+            enabled: false,
+        };
+        pwm.set_inactive();
+
+        pwm
+    }
+
+    pub fn set_frequency(&mut self, frequency: f64) -> rppal::gpio::Result<()> {
+        self.frequency = frequency;
+        if self.enabled {
+            self.pin
+                .set_pwm_frequency(self.frequency, self.output_duty())?;
+        }
+
+        Ok(())
+    }
+
+    pub fn polarity(&self) -> Polarity {
+        self.polarity
+    }
+
+    pub fn release(mut self) -> OutputPin {
+        let _ = self.pin.clear_pwm();
+        self.pin
+    }
+
+    /// The duty the pin is driven at, high time over period
+    fn output_duty(&self) -> f64 {
+        match self.polarity {
+            Polarity::Normal => self.duty,
+            Polarity::Inverse => 1.0 - self.duty,
+        }
+    }
+
+    fn set_inactive(&mut self) {
+        let _ = self.pin.clear_pwm();
+        match self.polarity {
+            Polarity::Normal => self.pin.set_low(),
+            Polarity::Inverse => self.pin.set_high(),
+        }
+    }
+}
+
+impl PwmPin for SoftPwm {
+    type Duty = f64;
+
+    fn disable(&mut self) {
+        self.enabled = false;
+        self.set_inactive();
+    }
+
+    fn enable(&mut self) {
+        self.enabled = true;
+        let _ = self
+            .pin
+            .set_pwm_frequency(self.frequency, self.output_duty());
+    }
+
+    fn get_duty(&self) -> f64 {
+        self.duty
+    }
+
+    fn get_max_duty(&self) -> f64 {
+        1.0
+    }
+
+    fn set_duty(&mut self, duty: f64) {
+        self.duty = duty.clamp(0.0, 1.0);
+        if self.enabled {
+            let _ = self
+                .pin
+                .set_pwm_frequency(self.frequency, self.output_duty());
+        }
+    }
+}
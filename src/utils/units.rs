@@ -0,0 +1,46 @@
+//! Typed sensor values, so a temperature can't be mixed up with a humidity
+
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Temperature(f64);
+
+impl Temperature {
+    pub fn from_celsius(celsius: f64) -> Self {
+        Self(celsius)
+    }
+
+    pub fn celsius(self) -> f64 {
+        self.0
+    }
+
+    pub fn fahrenheit(self) -> f64 {
+        self.0 * 9.0 / 5.0 + 32.0
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} °C", self.0)
+    }
+}
+
+/// Relative humidity
+#[derive(Copy, Clone, Debug, PartialEq, PartialOrd)]
+pub struct Humidity(f64);
+
+impl Humidity {
+    pub fn from_percent(percent: f64) -> Self {
+        Self(percent)
+    }
+
+    pub fn percent(self) -> f64 {
+        self.0
+    }
+}
+
+impl fmt::Display for Humidity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.1} %", self.0)
+    }
+}